@@ -1,12 +1,37 @@
 let storage = function (importObject) {
-  importObject.env.js_get_high_score = function () {
-    const score = localStorage.getItem('blocks_high_score') || '0';
-    return parseInt(score);
+  // Keys are passed as UTF-8 strings in wasm memory
+  const read_key = function (ptr, len) {
+    const bytes = new Uint8Array(wasm_memory.buffer, ptr, len);
+    return 'blocks_' + new TextDecoder().decode(bytes);
   };
 
-  importObject.env.js_save_high_score = function (score) {
-    localStorage.setItem('blocks_high_score', score.toString());
+  importObject.env.js_get_value = function (key_ptr, key_len, fallback) {
+    const value = localStorage.getItem(read_key(key_ptr, key_len));
+    return value === null ? fallback : parseInt(value);
+  };
+
+  importObject.env.js_save_value = function (key_ptr, key_len, value) {
+    localStorage.setItem(read_key(key_ptr, key_len), value.toString());
   };
 };
 miniquad_add_plugin({ register_plugin: storage, version: 1, name: "storage" });
 
+let focus = function (importObject) {
+  // Set when the tab is hidden or the window loses focus, cleared once read
+  let focus_lost = false;
+  document.addEventListener('visibilitychange', function () {
+    if (document.hidden) {
+      focus_lost = true;
+    }
+  });
+  window.addEventListener('blur', function () {
+    focus_lost = true;
+  });
+
+  importObject.env.js_focus_lost = function () {
+    const lost = focus_lost;
+    focus_lost = false;
+    return lost ? 1 : 0;
+  };
+};
+miniquad_add_plugin({ register_plugin: focus, version: 1, name: "focus" });
@@ -0,0 +1,246 @@
+use macroquad::prelude::*;
+use miniquad::date;
+
+use smallvec::SmallVec;
+
+use crate::{
+    config::{MARATHON, TIMING},
+    event::GameEvent,
+    focus,
+    game::Game,
+    input::{InputHandler, InputState},
+    mode::GameMode,
+    renderer::Renderer,
+    ruleset::Ruleset,
+    settings::{Settings, SettingsItem},
+    state::GameStatus,
+    storage,
+};
+
+// Macroquad frontend: feeds window input into the game core at a fixed tick
+// rate and draws the resulting state
+pub struct App {
+    game: Game,
+    // Frame time not yet simulated
+    accumulator: f32,
+    // Inputs waiting for the next tick, when a fast display runs several frames per tick
+    pending_inputs: SmallVec<[InputState; 4]>,
+    renderer: Renderer,
+    input: InputHandler,
+    settings: Settings,
+    show_settings: bool,
+    rulesets: Vec<Ruleset>,
+    ruleset_index: usize,
+    mode_index: usize,
+}
+
+impl App {
+    pub fn new(seed: u64, rulesets: Vec<Ruleset>, ruleset_index: usize) -> Self {
+        let game = Game::new(seed, rulesets[ruleset_index].clone());
+        let mut app = Self {
+            renderer: Renderer::new(&game.rules().board),
+            game,
+            accumulator: 0.0,
+            pending_inputs: SmallVec::new(),
+            input: InputHandler::new(),
+            settings: Settings::load(),
+            show_settings: false,
+            rulesets,
+            ruleset_index,
+            mode_index: 0,
+        };
+        app.load_records();
+        app
+    }
+
+    pub fn update(&mut self) {
+        if is_key_pressed(KeyCode::G) {
+            self.settings.ghost_piece = !self.settings.ghost_piece;
+            self.settings.save();
+        }
+
+        let inputs = self
+            .input
+            .update(&self.settings, self.game.rules().board.width);
+        let focus_lost = focus::focus_lost();
+        match self.game.state.status {
+            GameStatus::Start if self.show_settings => {
+                if let Some(item) = self.renderer.check_settings_click(&self.settings) {
+                    match item {
+                        SettingsItem::Back => self.show_settings = false,
+                        _ => {
+                            self.settings.cycle(item);
+                            self.settings.save();
+                        }
+                    }
+                }
+            }
+            GameStatus::Start => match self.renderer.check_click(GameStatus::Start, &self.game) {
+                Some(0) => {
+                    self.game
+                        .set_soft_drop_factor(self.settings.soft_drop_factor as f32);
+                    self.game.start();
+                    self.accumulator = 0.0;
+                    self.pending_inputs.clear();
+                }
+                Some(1) => self.next_mode(),
+                // The start level button is only there for modes that have one
+                Some(2) if self.game.mode().has_start_level() => self.next_start_level(),
+                Some(index) if index == 2 + self.game.mode().has_start_level() as usize => {
+                    self.next_ruleset()
+                }
+                Some(_) => self.show_settings = true,
+                None => {}
+            },
+            // Pause on request, or when the window was in the background
+            GameStatus::Playing if inputs.contains(&InputState::Pause) || focus_lost => {
+                self.game.pause();
+                self.pending_inputs.clear();
+            }
+            GameStatus::Playing => {
+                self.pending_inputs
+                    .retain(|&mut input| input != InputState::SoftDrop);
+                self.pending_inputs.extend(inputs);
+                self.run_ticks(get_frame_time());
+            }
+            GameStatus::Paused => {
+                let clicked = self.renderer.check_click(GameStatus::Paused, &self.game);
+                if inputs.contains(&InputState::Pause) {
+                    self.game.resume();
+                } else {
+                    match clicked {
+                        Some(0) => self.game.resume(),
+                        Some(1) => {
+                            self.save_high_score();
+                            self.game.restart();
+                        }
+                        Some(_) => {
+                            self.save_high_score();
+                            self.new_game();
+                        }
+                        None => {}
+                    }
+                }
+                self.accumulator = 0.0;
+            }
+            status @ (GameStatus::GameOver | GameStatus::Finished) => {
+                match self.renderer.check_click(status, &self.game) {
+                    Some(0) => {
+                        self.game.restart();
+                        self.accumulator = 0.0;
+                        self.pending_inputs.clear();
+                    }
+                    Some(_) => self.new_game(),
+                    None => {}
+                }
+            }
+        }
+        self.handle_events();
+    }
+
+    fn handle_events(&mut self) {
+        for event in self.game.take_events() {
+            self.renderer.handle_event(&event);
+            match event {
+                // Don't carry a held drop over to the next piece
+                GameEvent::PieceLocked => self.input.reset(),
+                GameEvent::GameOver => self.save_high_score(),
+                GameEvent::Finished => {
+                    self.save_high_score();
+                    self.save_best();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Runs as many fixed ticks as this frame's time covers. Inputs go into
+    // the first one, so none are lost or applied twice, while a held soft
+    // drop keeps going through all of them.
+    fn run_ticks(&mut self, frame_time: f32) {
+        let step = 1.0 / TIMING.tick_rate;
+        let soft_drop = self.pending_inputs.contains(&InputState::SoftDrop);
+        self.accumulator += frame_time.min(TIMING.max_frame_time);
+        while self.accumulator >= step {
+            self.accumulator -= step;
+            self.game.tick(step, &self.pending_inputs);
+            self.pending_inputs.clear();
+            if soft_drop {
+                self.pending_inputs.push(InputState::SoftDrop);
+            }
+            if self.game.state.status != GameStatus::Playing {
+                break;
+            }
+        }
+    }
+
+    pub fn draw(&mut self) {
+        // How far the frame is between the last tick and the next one
+        let alpha = self.accumulator * TIMING.tick_rate;
+        self.renderer
+            .draw(&self.game, &self.settings, self.show_settings, alpha);
+    }
+
+    fn next_mode(&mut self) {
+        self.mode_index = (self.mode_index + 1) % GameMode::ALL.len();
+        self.game.set_mode(GameMode::ALL[self.mode_index]);
+        self.load_records();
+    }
+
+    fn next_start_level(&mut self) {
+        let level = (self.game.start_level() + 1) % MARATHON.max_start_level;
+        self.game.set_start_level(level);
+    }
+
+    // Cycles to the next ruleset, which needs a fresh game
+    fn next_ruleset(&mut self) {
+        self.ruleset_index = (self.ruleset_index + 1) % self.rulesets.len();
+        self.new_game();
+    }
+
+    // Back to the start screen with a fresh game
+    fn new_game(&mut self) {
+        let start_level = self.game.start_level();
+        self.game = Game::new(
+            date::now() as u64,
+            self.rulesets[self.ruleset_index].clone(),
+        );
+        self.game.set_mode(GameMode::ALL[self.mode_index]);
+        self.game.set_start_level(start_level);
+        self.load_records();
+    }
+
+    // High score and personal best time of the selected mode, the time
+    // stored in milliseconds
+    fn load_records(&mut self) {
+        self.game.state.score.highest =
+            storage::get_high_score(&self.game.mode().high_score_key(self.game.rules()));
+        self.game.state.clock.best = self
+            .game
+            .mode()
+            .record_key(self.game.rules())
+            .map(|key| storage::get_value(&key, 0))
+            .filter(|&millis| millis > 0)
+            .map(|millis| millis as f64 / 1000.0);
+    }
+
+    fn save_best(&self) {
+        let clock = &self.game.state.clock;
+        if let (true, Some(key), Some(best)) = (
+            clock.new_best,
+            self.game.mode().record_key(self.game.rules()),
+            clock.best,
+        ) {
+            storage::set_value(&key, (best * 1000.0).round() as u32);
+        }
+    }
+
+    fn save_high_score(&self) {
+        let key = self.game.mode().high_score_key(self.game.rules());
+        let last_highscore = storage::get_high_score(&key);
+        let new_highscore = self.game.state.score.highest;
+        if new_highscore > last_highscore {
+            storage::update_high_score(&key, new_highscore);
+        }
+    }
+}
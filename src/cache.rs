@@ -56,7 +56,7 @@ impl TextCache {
 
         // Cache all single digit measurements
         for i in 0..10 {
-            self.number_widths[i] = measure_text(&i.to_string(), None, font_size, 1.0);
+            self.number_widths[i] = measure_text(i.to_string(), None, font_size, 1.0);
         }
     }
 
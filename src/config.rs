@@ -1,3 +1,5 @@
+use serde::Deserialize;
+
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
 pub struct Time(pub f64);
 
@@ -9,71 +11,293 @@ impl std::ops::Sub for Time {
     }
 }
 
+// Fields left out of a ruleset's `[board]` keep the guideline size
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct BoardDimensions {
     pub width: i32,
     pub height: i32,
+    // Rows above the visible field where blocks are kept but not drawn
+    pub hidden_rows: i32,
+}
+
+impl Default for BoardDimensions {
+    fn default() -> Self {
+        BOARD
+    }
 }
 
 pub const BOARD: BoardDimensions = BoardDimensions {
     width: 10,
     height: 20,
+    hidden_rows: 20,
 };
 
 pub struct GameTiming {
     pub line_clearing: f32,
+    pub callout: f32,
     pub flashing_intervall: f64,
+    // Simulation steps per second, independent of the display refresh rate
+    pub tick_rate: f32,
+    // Longest frame that is simulated in full, so a hitch can't snowball
+    pub max_frame_time: f32,
 }
 
 pub const TIMING: GameTiming = GameTiming {
     line_clearing: 0.5,
+    callout: 1.5,
     flashing_intervall: 10.0,
+    tick_rate: 60.0,
+    max_frame_time: 0.25,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockResetMode {
+    // Any successful move or rotation restarts the lock delay, up to `max_resets` times
+    #[default]
+    Move,
+    // Only moving down a row restarts the lock delay
+    Step,
+    // The lock delay never restarts
+    None,
+}
+
+// What topping out does, depending on the game mode
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TopOutPolicy {
+    GameOver,
+    // Empty the upper half of the field and the hidden rows and keep playing
+    ClearUpperHalf,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockConfig {
+    pub reset_mode: LockResetMode,
+    pub max_resets: u32,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        LOCK
+    }
+}
+
+pub const LOCK: LockConfig = LockConfig {
+    reset_mode: LockResetMode::Move,
+    max_resets: 15,
 };
 
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TopOutConfig {
+    // A new piece overlaps blocks where it spawns. When off, the piece is
+    // pushed up into the hidden rows until it fits.
+    pub block_out: bool,
+    // A piece locks completely above the visible field
+    pub lock_out: bool,
+    // A piece locks with any block above the visible field
+    pub partial_lock_out: bool,
+}
+
+impl Default for TopOutConfig {
+    fn default() -> Self {
+        TOP_OUT
+    }
+}
+
+pub const TOP_OUT: TopOutConfig = TopOutConfig {
+    block_out: true,
+    lock_out: true,
+    partial_lock_out: false,
+};
+
+pub struct SprintConfig {
+    // Lines between split times
+    pub split_lines: u32,
+}
+
+pub const SPRINT: SprintConfig = SprintConfig { split_lines: 10 };
+
+pub struct ZenConfig {
+    // Seconds per row when Zen has gravity at all
+    pub fall_interval: f32,
+}
+
+pub const ZEN: ZenConfig = ZenConfig { fall_interval: 3.0 };
+
+pub struct DigConfig {
+    // Seconds between rows when the garbage keeps rising
+    pub rise_interval: f32,
+}
+
+pub const DIG: DigConfig = DigConfig { rise_interval: 6.0 };
+
+pub struct MarathonConfig {
+    // Lines to clear in the Marathon with a goal
+    pub goal_lines: u32,
+    // Highest level to start on, one-based
+    pub max_start_level: usize,
+}
+
+pub const MARATHON: MarathonConfig = MarathonConfig {
+    goal_lines: 150,
+    max_start_level: 15,
+};
+
+pub struct QueueConfig {
+    pub preview_count: usize,
+}
+
+pub const QUEUE: QueueConfig = QueueConfig { preview_count: 5 };
+
 pub struct InputConfig {
     pub swipe_threshold: f32,
     pub hold_threshold: Time,
-    pub move_cooldown: Time,
     pub touch_threshold: Time,
     pub move_cooldown_swipe: Time,
     pub move_cooldown_hold: Time,
+    // Share of the screen height at the top where a tap holds the piece
+    pub hold_zone: f32,
 }
 
 pub const INPUT: InputConfig = InputConfig {
     swipe_threshold: 30.0,
     hold_threshold: Time(0.2),
-    move_cooldown: Time(0.1),
     touch_threshold: Time(0.15),
     move_cooldown_swipe: Time(0.2),
     move_cooldown_hold: Time(0.1),
+    hold_zone: 0.2,
 };
 
 pub struct UiText {
     pub game_name: &'static str,
     pub start: &'static str,
     pub start_button: &'static str,
+    pub settings_button: &'static str,
+    pub ruleset: &'static str,
     pub gameover: &'static str,
     pub gameover_button: &'static str,
+    pub paused: &'static str,
+    pub resume_button: &'static str,
+    pub restart_button: &'static str,
+    pub quit_button: &'static str,
+    pub finished: &'static str,
+    pub mode: &'static str,
+    pub endless: &'static str,
+    pub marathon: &'static str,
+    pub start_level: &'static str,
+    pub sprint: &'static str,
+    pub ultra: &'static str,
+    pub zen: &'static str,
+    pub dig: &'static str,
+    pub rising: &'static str,
+    pub garbage: &'static str,
+    pub pieces: &'static str,
+    pub no_gravity: &'static str,
+    pub time_up: &'static str,
+    pub remaining: &'static str,
+    pub time: &'static str,
+    pub lines: &'static str,
+    pub best_time: &'static str,
+    pub new_best_time: &'static str,
     pub score: &'static str,
     pub level: &'static str,
     pub highscore: &'static str,
+    pub hold: &'static str,
+    pub next: &'static str,
+    pub settings: &'static str,
+    pub back_button: &'static str,
+    pub ghost: &'static str,
+    pub das: &'static str,
+    pub arr: &'static str,
+    pub soft_drop: &'static str,
+    pub tspin: &'static str,
+    pub tspin_mini: &'static str,
+    pub clear_names: [&'static str; 5],
+    pub back_to_back: &'static str,
+    pub perfect_clear: &'static str,
+    pub combo: &'static str,
+    pub back_to_back_counter: &'static str,
+    pub on: &'static str,
+    pub off: &'static str,
 }
 
 pub const TEXT: UiText = UiText {
     game_name: "Blocks",
     start: "",
     start_button: "Start",
+    settings_button: "Einstellungen",
+    ruleset: "Regeln: ",
     gameover: "Spiel vorbei",
     gameover_button: "Neu starten",
+    paused: "Pause",
+    resume_button: "Weiter",
+    restart_button: "Neu starten",
+    quit_button: "Beenden",
+    finished: "Geschafft!",
+    mode: "Modus: ",
+    endless: "Endlos",
+    marathon: "Marathon",
+    start_level: "Startlevel: ",
+    sprint: "Sprint",
+    ultra: "Ultra",
+    zen: "Zen",
+    dig: "Graben",
+    rising: "steigend",
+    garbage: "Müll: ",
+    pieces: "Teile: ",
+    no_gravity: "ohne Fallen",
+    time_up: "Zeit abgelaufen!",
+    remaining: "Rest: ",
+    time: "Zeit: ",
+    lines: "Linien: ",
+    best_time: "Bestzeit: ",
+    new_best_time: "Neue Bestzeit!",
     score: "Score: ",
     level: "Level: ",
     highscore: "Highscore: ",
+    hold: "Hold",
+    next: "Next",
+    settings: "Einstellungen",
+    back_button: "Zurück",
+    ghost: "Schatten: ",
+    das: "DAS: ",
+    arr: "ARR: ",
+    soft_drop: "Soft Drop: ",
+    tspin: "T-SPIN",
+    tspin_mini: "T-SPIN MINI",
+    clear_names: ["", "SINGLE", "DOUBLE", "TRIPLE", "TETRIS"],
+    back_to_back: "B2B",
+    perfect_clear: "PERFECT CLEAR",
+    combo: "Combo: ",
+    back_to_back_counter: "B2B: ",
+    on: "An",
+    off: "Aus",
 };
 
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ScoreConfig {
     pub single: u32,
     pub double: u32,
     pub triple: u32,
     pub tetris: u32,
+    pub tspin_mini: u32,
+    pub tspin_mini_single: u32,
+    pub tspin_mini_double: u32,
+    pub tspin: u32,
+    pub tspin_single: u32,
+    pub tspin_double: u32,
+    pub tspin_triple: u32,
+    pub combo: u32,
+    pub back_to_back_multiplier: f32,
+    pub perfect_clear: [u32; 4],
+    pub perfect_clear_back_to_back_tetris: u32,
+    // Points per cell dropped
+    pub soft_drop: u32,
+    pub hard_drop: u32,
 }
 
 pub const SCORE: ScoreConfig = ScoreConfig {
@@ -81,63 +305,119 @@ pub const SCORE: ScoreConfig = ScoreConfig {
     double: 300,
     triple: 500,
     tetris: 800,
+    tspin_mini: 100,
+    tspin_mini_single: 200,
+    tspin_mini_double: 400,
+    tspin: 400,
+    tspin_single: 800,
+    tspin_double: 1200,
+    tspin_triple: 1600,
+    combo: 50,
+    back_to_back_multiplier: 1.5,
+    perfect_clear: [800, 1200, 1800, 2000],
+    perfect_clear_back_to_back_tetris: 3200,
+    soft_drop: 1,
+    hard_drop: 2,
 };
 
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        SCORE
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct LevelConfig {
-    pub fall_interval: f32,
+    // Seconds per row, the guideline curve when left out
+    #[serde(default)]
+    pub fall_interval: Option<f32>,
     pub lines_required: u32,
+    #[serde(default = "default_score_multiplier")]
     pub score_multiplier: f32,
+    #[serde(default = "default_lock_delay")]
+    pub lock_delay: f32,
+}
+
+fn default_score_multiplier() -> f32 {
+    1.0
 }
 
+fn default_lock_delay() -> f32 {
+    0.5
+}
+
+pub struct GravityConfig {
+    // Levels past the end of a level table each take this many more lines
+    pub lines_per_level: u32,
+    // First level where the guideline curve drops pieces instantly (20G)
+    pub instant_level: usize,
+}
+
+pub const GRAVITY: GravityConfig = GravityConfig {
+    lines_per_level: 10,
+    instant_level: 20,
+};
+
 pub const LEVEL_CONFIGS: [LevelConfig; 10] = [
     LevelConfig {
-        fall_interval: 0.48,
+        fall_interval: None,
         lines_required: 10,
         score_multiplier: 1.0,
+        lock_delay: 0.50,
     }, // Level 1
     LevelConfig {
-        fall_interval: 0.40,
+        fall_interval: None,
         lines_required: 20,
         score_multiplier: 1.5,
+        lock_delay: 0.50,
     }, // Level 2
     LevelConfig {
-        fall_interval: 0.32,
+        fall_interval: None,
         lines_required: 30,
         score_multiplier: 2.0,
+        lock_delay: 0.50,
     }, // Level 3
     LevelConfig {
-        fall_interval: 0.24,
+        fall_interval: None,
         lines_required: 40,
         score_multiplier: 2.5,
+        lock_delay: 0.50,
     }, // Level 4
     LevelConfig {
-        fall_interval: 0.16,
+        fall_interval: None,
         lines_required: 50,
         score_multiplier: 3.0,
+        lock_delay: 0.50,
     }, // Level 5
     LevelConfig {
-        fall_interval: 0.14,
+        fall_interval: None,
         lines_required: 60,
         score_multiplier: 3.5,
+        lock_delay: 0.50,
     }, // Level 6
     LevelConfig {
-        fall_interval: 0.12,
+        fall_interval: None,
         lines_required: 70,
         score_multiplier: 4.0,
+        lock_delay: 0.50,
     }, // Level 7
     LevelConfig {
-        fall_interval: 0.11,
+        fall_interval: None,
         lines_required: 80,
         score_multiplier: 4.5,
+        lock_delay: 0.45,
     }, // Level 8
     LevelConfig {
-        fall_interval: 0.10,
+        fall_interval: None,
         lines_required: 90,
         score_multiplier: 4.7,
+        lock_delay: 0.40,
     }, // Level 9
     LevelConfig {
-        fall_interval: 0.10,
+        fall_interval: None,
         lines_required: 100,
         score_multiplier: 5.0,
+        lock_delay: 0.35,
     }, // Level 10
 ];
@@ -0,0 +1,22 @@
+use crate::{state::ClearKind, tetromino::Tetromino};
+
+// Things that happened during a tick. The game queues them up and the
+// frontend drains the queue once per frame.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    PieceSpawned(Tetromino),
+    // Shifted left or right
+    PieceMoved,
+    PieceRotated,
+    PieceLocked,
+    LinesCleared { count: u32, kind: ClearKind },
+    LevelUp(usize),
+    HoldUsed,
+    GameOver,
+    // Blocks were cleared away after a top-out, in modes that keep going
+    BoardCleared,
+    // A garbage row rose from below
+    GarbageAdded,
+    // The goal of the game mode was reached
+    Finished,
+}
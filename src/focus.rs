@@ -0,0 +1,44 @@
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn js_focus_lost() -> u32;
+}
+
+// Desktop windows report losing focus or being minimized as a minimize event
+#[cfg(not(target_arch = "wasm32"))]
+fn window_focus_lost() -> bool {
+    use macroquad::input::utils::{register_input_subscriber, repeat_all_miniquad_input};
+    use macroquad::miniquad::EventHandler;
+
+    struct FocusWatcher {
+        lost: bool,
+    }
+
+    impl EventHandler for FocusWatcher {
+        fn update(&mut self) {}
+
+        fn draw(&mut self) {}
+
+        fn window_minimized_event(&mut self) {
+            self.lost = true;
+        }
+    }
+
+    thread_local! {
+        static SUBSCRIBER: usize = register_input_subscriber();
+    }
+
+    let mut watcher = FocusWatcher { lost: false };
+    SUBSCRIBER.with(|&subscriber| repeat_all_miniquad_input(&mut watcher, subscriber));
+    watcher.lost
+}
+
+// Whether the browser tab or the window was hidden or lost focus since the last call
+pub fn focus_lost() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    unsafe {
+        js_focus_lost() != 0
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    window_focus_lost()
+}
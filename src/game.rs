@@ -1,293 +1,1141 @@
 use crate::{
-    config::{BOARD, LEVEL_CONFIGS, SCORE, TIMING},
-    input::{InputHandler, InputState},
-    renderer::Renderer,
-    state::{GameState, GameStatus},
-    storage,
+    config::{LevelConfig, LockResetMode, TopOutPolicy, MARATHON, SPRINT, TIMING},
+    dummy_board::DummyBoard,
+    event::GameEvent,
+    garbage,
+    input::InputState,
+    mode::GameMode,
+    randomizer::Randomizer,
+    rng::Rng,
+    ruleset::Ruleset,
+    state::{Cell, ClearKind, GameState, GameStatus, LineClear, PieceState},
     tetromino::{RotationState, Tetromino},
 };
-use macroquad::prelude::*;
 use smallvec::SmallVec;
 
+// Headless simulation core. It never touches the window, clock or storage,
+// so the same seed and inputs always produce the same game.
 pub struct Game {
     pub state: GameState,
-    pub renderer: Renderer,
-    pub input: InputHandler,
+    rng: Rng,
+    rules: Ruleset,
+    mode: GameMode,
+    // Zero-based level a new game begins on
+    start_level: usize,
+    randomizer: Box<dyn Randomizer>,
+    events: Vec<GameEvent>,
+    // Inputs pressed while cleared lines were flashing, applied once the
+    // next piece is released
+    buffered_inputs: SmallVec<[InputState; 4]>,
+    soft_drop_factor: f32,
+    soft_dropping: bool,
 }
+
 impl Game {
-    pub fn new() -> Self {
+    pub fn new(seed: u64, rules: Ruleset) -> Self {
+        let mut rng = Rng::new(seed);
+        let dummy_board =
+            DummyBoard::new(&mut rng, rules.randomizer.create().as_mut(), &rules.board);
+        let mut randomizer = rules.randomizer.create();
+        let state = Self::new_state(&mut rng, randomizer.as_mut(), &rules, Some(dummy_board));
         Self {
-            state: GameState::new(),
-            renderer: Renderer::new(),
-            input: InputHandler::new(),
+            state,
+            rng,
+            rules,
+            mode: GameMode::Marathon {
+                goal: Some(MARATHON.goal_lines),
+            },
+            start_level: 0,
+            randomizer,
+            events: Vec::new(),
+            buffered_inputs: SmallVec::new(),
+            soft_drop_factor: 20.0,
+            soft_dropping: false,
+        }
+    }
+
+    fn new_state(
+        rng: &mut Rng,
+        randomizer: &mut dyn Randomizer,
+        rules: &Ruleset,
+        dummy_board: Option<DummyBoard>,
+    ) -> GameState {
+        let initial_piece = randomizer.next(rng);
+        let queue = (0..rules.preview_count)
+            .map(|_| randomizer.next(rng))
+            .collect();
+        GameState::new(initial_piece, queue, &rules.board, dummy_board)
+    }
+
+    pub fn rules(&self) -> &Ruleset {
+        &self.rules
+    }
+
+    // Events since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.mode = mode;
+    }
+
+    pub fn start_level(&self) -> usize {
+        self.start_level
+    }
+
+    pub fn set_start_level(&mut self, level: usize) {
+        self.start_level = level;
+    }
+
+    // The picked start level, for modes that offer one
+    fn initial_level(&self) -> usize {
+        if self.mode.has_start_level() {
+            self.start_level
+        } else {
+            0
+        }
+    }
+
+    pub fn set_soft_drop_factor(&mut self, factor: f32) {
+        self.soft_drop_factor = factor.max(1.0);
+    }
+
+    pub fn start(&mut self) {
+        self.state.dummy_board = None;
+        self.state.status = GameStatus::Playing;
+        self.state.level.current = self.initial_level();
+
+        // Leave the top rows free so the first piece can enter
+        let board = &mut self.state.board.cells;
+        let rows = self.mode.garbage_rows().min(board.height as u32 - 2);
+        for _ in 0..rows {
+            garbage::add_row(board, &mut self.rng);
+        }
+
+        self.enter_piece(self.state.piece.typ);
+    }
+
+    // Garbage rows still on the board, not counting those being cleared
+    pub fn garbage_left(&self) -> usize {
+        let board = &self.state.board;
+        (board.cells.top()..board.cells.height)
+            .filter(|y| !board.flashing_lines.contains(y))
+            .filter(|&y| board.cells.row(y).contains(&Some(Cell::Garbage)))
+            .count()
+    }
+
+    pub fn pause(&mut self) {
+        if self.state.status == GameStatus::Playing {
+            self.state.status = GameStatus::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.state.status == GameStatus::Paused {
+            self.state.status = GameStatus::Playing;
         }
     }
 
+    pub fn restart(&mut self) {
+        let high_score = self.state.score.highest;
+        let best = self.state.clock.best;
+        self.randomizer = self.rules.randomizer.create();
+        let mut new_state =
+            Self::new_state(&mut self.rng, self.randomizer.as_mut(), &self.rules, None);
+        new_state.score.highest = high_score;
+        new_state.clock.best = best;
+        self.state = new_state;
+        self.events.clear();
+        self.buffered_inputs.clear();
+        self.start();
+    }
+
+    // Advances the simulation by `delta` seconds after applying this tick's inputs
+    pub fn tick(&mut self, delta: f32, inputs: &[InputState]) {
+        if self.state.status != GameStatus::Playing {
+            return;
+        }
+
+        self.state.piece.previous_position = self.state.piece.position;
+        self.state.clock.elapsed += delta as f64;
+        if let Some(limit) = self.mode.time_limit() {
+            if self.state.clock.elapsed >= limit {
+                self.state.clock.elapsed = limit;
+                self.finish();
+                return;
+            }
+        }
+
+        // The piece waits while cleared lines are still on the board
+        self.soft_dropping = false;
+        if self.state.board.flashing_lines.is_empty() {
+            self.state.timing.fall_interval = self.fall_interval();
+            let buffered = std::mem::take(&mut self.buffered_inputs);
+            for &input in buffered.iter().chain(inputs) {
+                self.handle_input(input);
+                if self.state.status != GameStatus::Playing {
+                    return;
+                }
+            }
+        } else {
+            // A held soft drop is sent again every tick, so it isn't kept
+            self.buffered_inputs
+                .extend(inputs.iter().copied().filter(|&input| {
+                    !matches!(
+                        input,
+                        InputState::SoftDrop | InputState::Pause | InputState::None
+                    )
+                }));
+        }
+        self.update_gameplay(delta);
+    }
+
+    // Takes the front of the queue and refills it from the randomizer
+    fn next_piece(&mut self) -> Tetromino {
+        self.state
+            .queue
+            .push_back(self.randomizer.next(&mut self.rng));
+        self.state.queue.pop_front().unwrap()
+    }
+
     fn spawn_piece(&mut self) {
-        self.state.piece.typ = Tetromino::random();
-        let shape = self.state.piece.typ.shape();
-        let piece_width = shape.iter().map(|(x, _)| x).max().unwrap()
-            - shape.iter().map(|(x, _)| x).min().unwrap()
-            + 1;
-        self.state.piece.position = (BOARD.width / 2 - piece_width / 2, -1);
-        self.state.piece.rotation = RotationState::Zero;
-        self.state.piece.rotated = self.get_rotated_shape();
+        let piece = self.next_piece();
+        self.enter_piece(piece);
     }
 
-    fn get_rotated_shape(&self) -> [(i32, i32); 4] {
-        let shape = self.state.piece.typ.shape();
-        let pivot = (1, 1);
+    fn enter_piece(&mut self, typ: Tetromino) {
+        self.state.piece = PieceState::spawn(typ, self.rules.board.width);
+        self.state.timing.fall_timer = 0.0;
+        self.state.timing.lock_timer = 0.0;
+        self.state.timing.lock_resets = 0;
+
+        // Block out, or push the piece up through the hidden rows until it fits
+        if !self.is_valid_position() {
+            if self.rules.top_out.block_out {
+                if self.top_out() {
+                    self.enter_piece(typ);
+                }
+                return;
+            }
+            let top = self.state.board.cells.top();
+            let highest = self
+                .state
+                .piece
+                .rotated
+                .iter()
+                .map(|&(_, y)| y)
+                .min()
+                .unwrap();
+            while !self.is_valid_position() {
+                self.state.piece.position.1 -= 1;
+                if self.state.piece.position.1 + highest < top {
+                    if self.top_out() {
+                        self.enter_piece(typ);
+                    }
+                    return;
+                }
+            }
+        }
 
-        // Using array methods directly
-        let moved_center = shape.map(|(x, y)| (x - pivot.0, y - pivot.1));
+        // The piece enters the field right away unless something is in the way
+        if self.can_move(0, 1) {
+            self.state.piece.position.1 += 1;
+        }
+        self.state.timing.lowest_row = self.state.piece.position.1;
+        self.state.piece.previous_position = self.state.piece.position;
+        self.events.push(GameEvent::PieceSpawned(typ));
+    }
 
-        match self.state.piece.rotation {
-            RotationState::Zero => shape,
-            RotationState::Right => moved_center.map(|(x, y)| (-y + pivot.0, x + pivot.1)),
-            RotationState::Two => moved_center.map(|(x, y)| (-x + pivot.0, -y + pivot.1)),
-            RotationState::Left => moved_center.map(|(x, y)| (y + pivot.0, -x + pivot.1)),
+    fn finish(&mut self) {
+        // Only modes racing the clock keep a best time
+        if self.mode.has_best_time() {
+            let clock = &mut self.state.clock;
+            clock.new_best = clock.best.is_none_or(|best| clock.elapsed < best);
+            if clock.new_best {
+                clock.best = Some(clock.elapsed);
+            }
         }
+        self.state.status = GameStatus::Finished;
+        self.events.push(GameEvent::Finished);
     }
 
-    fn can_move(&self, dx: i32, dy: i32) -> bool {
-        for &(x, y) in &self.state.piece.rotated {
-            let new_x = self.state.piece.position.0 + x + dx;
-            let new_y = self.state.piece.position.1 + y + dy;
-            if !(0..BOARD.width).contains(&new_x)
-                || new_y >= BOARD.height
-                || (new_y >= 0 && self.state.board.cells[new_y as usize][new_x as usize].is_some())
-            {
+    // Whether the game mode's goal is met. Runs right after the lines are
+    // marked for clearing, so lines still flashing already count.
+    fn goal_reached(&self) -> bool {
+        match self.mode {
+            GameMode::Dig { .. } => self.garbage_left() == 0,
+            mode => mode
+                .line_goal()
+                .is_some_and(|goal| self.state.level.total_lines_cleared >= goal),
+        }
+    }
+
+    // Pushes a garbage row in from below and lifts the falling piece along
+    // when it is in the way. Returns whether play goes on.
+    fn add_garbage_row(&mut self) -> bool {
+        if !garbage::add_row(&mut self.state.board.cells, &mut self.rng) {
+            return self.top_out();
+        }
+        if !self.is_valid_position() {
+            let piece = &mut self.state.piece;
+            piece.position.1 -= 1;
+            piece.previous_position.1 -= 1;
+            self.state.timing.lowest_row -= 1;
+        }
+        self.events.push(GameEvent::GarbageAdded);
+        true
+    }
+
+    // Ends the game, or makes room on the board in modes that keep going.
+    // Returns whether play goes on.
+    fn top_out(&mut self) -> bool {
+        let board = &mut self.state.board.cells;
+        match self.mode.top_out_policy() {
+            TopOutPolicy::GameOver => {
+                self.game_over();
                 return false;
             }
+            TopOutPolicy::ClearUpperHalf => board.clear_above(board.height / 2),
         }
+        self.events.push(GameEvent::BoardCleared);
         true
     }
 
+    fn game_over(&mut self) {
+        self.state.status = GameStatus::GameOver;
+        self.events.push(GameEvent::GameOver);
+    }
+
+    fn get_rotated_shape(&self) -> [(i32, i32); 4] {
+        self.state.piece.typ.cells(self.state.piece.rotation)
+    }
+
+    fn can_move(&self, dx: i32, dy: i32) -> bool {
+        let piece = &self.state.piece;
+        self.state.board.fits(
+            &piece.rotated,
+            (piece.position.0 + dx, piece.position.1 + dy),
+        )
+    }
+
+    fn hard_drop(&mut self) {
+        let distance = self.state.drop_distance();
+        self.state.piece.position.1 += distance;
+        if distance > 0 {
+            self.state.piece.last_kick = None;
+        }
+        self.add_score(distance as u32 * self.rules.score.hard_drop);
+        self.place_piece();
+    }
+
+    fn place_piece(&mut self) {
+        let kind = self.detect_spin();
+        let locked_out = self.is_locked_out();
+        self.lock_piece();
+        if locked_out && !self.top_out() {
+            return;
+        }
+        self.clear_lines(kind);
+        if self.goal_reached() {
+            self.finish();
+            return;
+        }
+        self.state.hold.used = false;
+        // With lines cleared, the next piece enters once they are gone
+        if self.state.board.flashing_lines.is_empty() {
+            self.spawn_piece();
+        }
+    }
+
+    fn hold_piece(&mut self) {
+        if self.state.hold.used {
+            return;
+        }
+
+        // Swap with the held piece, or take the next one if the slot is empty
+        self.events.push(GameEvent::HoldUsed);
+        match self.state.hold.piece.replace(self.state.piece.typ) {
+            Some(held) => self.enter_piece(held),
+            None => self.spawn_piece(),
+        }
+        self.state.hold.used = true;
+    }
+
+    // Three-corner T-spin check: the last action must be a rotation and three of the
+    // four corners around the T's center must be blocked. Unless both corners in
+    // front of the flat side are blocked or the last kick of a 90° turn (the TST
+    // kick) was used, it is a mini.
+    fn detect_spin(&self) -> ClearKind {
+        let piece = &self.state.piece;
+        let Some(kick) = piece.last_kick else {
+            return ClearKind::Normal;
+        };
+        if piece.typ != Tetromino::T {
+            return ClearKind::Normal;
+        }
+
+        let (x, y) = piece.position;
+        let blocked = |(dx, dy): (i32, i32)| self.state.board.is_occupied(x + dx, y + dy);
+        let corners = [(0, 0), (2, 0), (2, 2), (0, 2)];
+        if corners
+            .into_iter()
+            .filter(|&corner| blocked(corner))
+            .count()
+            < 3
+        {
+            return ClearKind::Normal;
+        }
+
+        let front = match piece.rotation {
+            RotationState::Zero => [(0, 0), (2, 0)],
+            RotationState::Right => [(2, 0), (2, 2)],
+            RotationState::Two => [(2, 2), (0, 2)],
+            RotationState::Left => [(0, 2), (0, 0)],
+        };
+        if front.into_iter().all(blocked) || (kick == 4 && !piece.last_flip) {
+            ClearKind::TSpin
+        } else {
+            ClearKind::TSpinMini
+        }
+    }
+
     fn lock_piece(&mut self) {
-        for &(x, y) in &self.state.piece.rotated {
-            let board_x = self.state.piece.position.0 + x;
-            let board_y = self.state.piece.position.1 + y;
-            if board_y >= 0 {
-                self.state.board.cells[board_y as usize][board_x as usize] =
-                    Some(self.state.piece.typ.color());
-            }
+        let piece = &self.state.piece;
+        for &(x, y) in &piece.rotated {
+            self.state.board.cells.set(
+                piece.position.0 + x,
+                piece.position.1 + y,
+                Some(Cell::Piece(piece.typ)),
+            );
         }
-        self.input.reset();
+        self.state.pieces_placed += 1;
+        self.events.push(GameEvent::PieceLocked);
     }
 
-    fn clear_lines(&mut self) {
+    fn clear_lines(&mut self, kind: ClearKind) {
         let mut lines_to_clear = SmallVec::new();
 
         // Identify full lines
-        for y in 0..BOARD.height as u8 {
-            if self.state.board.cells[y as usize]
-                .iter()
-                .all(|&cell| cell.is_some())
-            {
+        let cells = &self.state.board.cells;
+        for y in cells.top()..cells.height {
+            if cells.is_row_full(y) {
                 lines_to_clear.push(y);
             }
         }
 
-        if !lines_to_clear.is_empty() {
-            let num_of_lines_to_clear = lines_to_clear.len() as u32;
+        let num_of_lines_to_clear = lines_to_clear.len() as u32;
+
+        // Tetrises and T-spin clears keep a back-to-back chain going, other clears break it
+        let difficult =
+            num_of_lines_to_clear == 4 || (num_of_lines_to_clear > 0 && kind != ClearKind::Normal);
+        let back_to_back = difficult && self.state.combo.back_to_back > 0;
+
+        // The combo counts every line clearing placement after the first
+        let combo = &mut self.state.combo;
+        if num_of_lines_to_clear > 0 {
+            combo.combo = Some(combo.combo.map_or(0, |combo| combo + 1));
+            combo.back_to_back = if difficult { combo.back_to_back + 1 } else { 0 };
+        } else {
+            combo.combo = None;
+        }
+
+        // T-spins score even without clearing a line
+        if num_of_lines_to_clear > 0 || kind != ClearKind::Normal {
+            let clear = LineClear {
+                lines: num_of_lines_to_clear,
+                kind,
+                back_to_back,
+                perfect_clear: num_of_lines_to_clear > 0 && self.is_perfect_clear(&lines_to_clear),
+            };
+            let score = self.calculate_score(clear);
+            self.add_score(score);
+            self.state.score.last_clear = Some(clear);
+            self.events.push(GameEvent::LinesCleared {
+                count: num_of_lines_to_clear,
+                kind,
+            });
+            self.state.timing.callout_timer = TIMING.callout;
+        }
 
+        if !lines_to_clear.is_empty() {
             // Start line clear animation
             self.state.board.flashing_lines = lines_to_clear;
             self.state.timing.line_clear_timer = TIMING.line_clearing;
 
-            // Calculate scrore
-            let score = self.calculate_score(num_of_lines_to_clear);
-            self.state.score.current += score;
-            self.state.score.highest = self.state.score.highest.max(self.state.score.current);
             self.state.level.total_lines_cleared += num_of_lines_to_clear;
 
             // Update level
             self.update_level();
-        }
-    }
 
-    fn is_game_over(&self) -> bool {
-        // Check if new piece overlaps with existing pieces
-        for &(x, y) in &self.state.piece.rotated {
-            let board_x = self.state.piece.position.0 + x;
-            let board_y = self.state.piece.position.1 + y;
-            if board_y >= 0 && self.state.board.cells[board_y as usize][board_x as usize].is_some()
-            {
-                return true;
+            let clock = &mut self.state.clock;
+            let total = self.state.level.total_lines_cleared;
+            while (clock.splits.len() as u32 + 1) * SPRINT.split_lines <= total {
+                clock.splits.push(clock.elapsed);
             }
         }
-        false
     }
 
-    pub fn update_logic(&mut self) {
-        match self.state.status {
-            GameStatus::Start => {
-                if self.renderer.check_click(GameStatus::Start) {
-                    self.state.dummy_board = None;
-                    self.state.status = GameStatus::Playing;
-                    self.renderer.mark_board_dirty();
-                }
-            }
-            GameStatus::Playing => self.update_gameplay(),
-            GameStatus::GameOver => {
-                if self.renderer.check_click(GameStatus::GameOver) {
-                    self.restart();
-                    self.state.status = GameStatus::Playing;
-                }
-            }
-        }
+    // Whether clearing these lines leaves the board empty
+    fn is_perfect_clear(&self, lines: &[i32]) -> bool {
+        let cells = &self.state.board.cells;
+        (cells.top()..cells.height).all(|y| lines.contains(&y) || cells.is_row_empty(y))
+    }
+
+    // Lock out and partial lock out: the piece rests above the visible field
+    fn is_locked_out(&self) -> bool {
+        let piece = &self.state.piece;
+        let above = piece
+            .rotated
+            .iter()
+            .filter(|&&(_, y)| piece.position.1 + y < 0)
+            .count();
+        let top_out = &self.rules.top_out;
+        (top_out.lock_out && above == piece.rotated.len())
+            || (top_out.partial_lock_out && above > 0)
     }
 
-    fn update_gameplay(&mut self) {
-        let delta = get_frame_time();
+    fn update_gameplay(&mut self, delta: f32) {
+        if self.state.timing.callout_timer > 0.0 {
+            self.state.timing.callout_timer -= delta;
+            if self.state.timing.callout_timer <= 0.0 {
+                self.state.score.last_clear = None;
+            }
+        }
 
         // Handle line clear animation
         if !self.state.board.flashing_lines.is_empty() {
             self.state.timing.line_clear_timer -= delta;
             if self.state.timing.line_clear_timer <= 0.0 {
-                // Remove lines after flashing
+                // Remove lines after flashing, then let the next piece in
                 self.remove_flashing_lines();
                 self.state.board.flashing_lines.clear();
+                self.spawn_piece();
             }
+            return;
         }
 
-        // Update timers
-        self.state.timing.fall_timer += delta;
+        // Dig mode garbage that keeps rising from below
+        if let Some(interval) = self.mode.garbage_interval() {
+            self.state.timing.garbage_timer += delta;
+            if self.state.timing.garbage_timer >= interval {
+                self.state.timing.garbage_timer -= interval;
+                if !self.add_garbage_row() {
+                    return;
+                }
+            }
+        }
 
-        // Handle automatic piece falling
-        if self.state.timing.fall_timer >= self.state.timing.fall_interval {
-            self.state.timing.fall_timer = 0.0;
-            if self.can_move(0, 1) {
-                self.state.piece.position.1 += 1;
-            } else {
-                self.lock_piece();
-                self.clear_lines();
-                self.renderer.mark_board_dirty();
-                self.spawn_piece();
+        // Handle automatic piece falling, several rows per tick at high gravity.
+        // A zero interval (20G) drops the piece straight to the floor.
+        let timing = &mut self.state.timing;
+        timing.fall_timer += delta;
+        let rows = if timing.fall_interval <= 0.0 {
+            timing.fall_timer = 0.0;
+            self.state.drop_distance()
+        } else {
+            let rows = (timing.fall_timer / timing.fall_interval) as i32;
+            timing.fall_timer -= rows as f32 * timing.fall_interval;
+            rows
+        };
+        for _ in 0..rows {
+            if !self.can_move(0, 1) {
+                // Resting on the ground doesn't bank time for later
+                self.state.timing.fall_timer = 0.0;
+                break;
+            }
+            self.state.piece.position.1 += 1;
+            self.state.piece.last_kick = None;
+            self.on_piece_fell();
+            if self.soft_dropping {
+                self.add_score(self.rules.score.soft_drop);
             }
         }
 
-        if self.is_game_over() {
-            let last_highscore = storage::get_high_score();
-            let new_highscore = self.state.score.highest;
-            if new_highscore > last_highscore {
-                storage::update_high_score(new_highscore);
+        // Lock once the piece has rested on the ground for the lock delay,
+        // or right away when it has used up its move resets
+        if !self.can_move(0, 1) {
+            self.state.timing.lock_timer += delta;
+            let resets_exhausted = self.rules.lock.reset_mode == LockResetMode::Move
+                && self.state.timing.lock_resets >= self.rules.lock.max_resets;
+            if self.state.timing.lock_timer >= self.level_config().lock_delay || resets_exhausted {
+                self.place_piece();
             }
-            self.state.status = GameStatus::GameOver;
         }
     }
 
-    pub fn handle_input(&mut self, input: InputState) {
+    fn handle_input(&mut self, input: InputState) {
         match input {
             InputState::MoveLeft => {
                 if self.can_move(-1, 0) {
                     self.state.piece.position.0 -= 1;
+                    self.state.piece.last_kick = None;
+                    self.on_piece_moved();
+                    self.events.push(GameEvent::PieceMoved);
                 }
             }
             InputState::MoveRight => {
                 if self.can_move(1, 0) {
                     self.state.piece.position.0 += 1;
+                    self.state.piece.last_kick = None;
+                    self.on_piece_moved();
+                    self.events.push(GameEvent::PieceMoved);
                 }
             }
-            InputState::Rotate => {
-                self.try_rotation();
+            InputState::RotateCw => {
+                self.try_rotation(self.state.piece.rotation.cw());
+            }
+            InputState::RotateCcw => {
+                self.try_rotation(self.state.piece.rotation.ccw());
+            }
+            InputState::Rotate180 => {
+                self.try_rotation(self.state.piece.rotation.flip());
+            }
+            InputState::SoftDrop => {
+                self.soft_dropping = true;
+                let base = self
+                    .mode
+                    .soft_drop_base()
+                    .unwrap_or_else(|| self.rules.fall_interval(self.state.level.current));
+                self.state.timing.fall_interval = base / self.soft_drop_factor;
             }
-            InputState::Drop => {
-                self.state.timing.fall_interval = 0.05; // Increase fall speed when dropping
+            InputState::HardDrop if self.rules.hard_drop => {
+                self.hard_drop();
             }
-            InputState::None => {
-                self.state.timing.fall_interval =
-                    LEVEL_CONFIGS[self.state.level.current].fall_interval;
+            InputState::Hold if self.rules.hold => {
+                self.hold_piece();
             }
+            InputState::HardDrop | InputState::Hold | InputState::Pause | InputState::None => {}
         }
     }
 
-    fn try_rotation(&mut self) {
-        if self.state.piece.typ == Tetromino::O {
-            return;
-        }
-
-        let original_x = self.state.piece.position.0;
-
-        let offsets = [0, -1, 1, -2, 2];
+    fn try_rotation(&mut self, rotation: RotationState) {
+        let original_position = self.state.piece.position;
+        let original_rotation = self.state.piece.rotation;
 
-        let temp_rotation = self.state.piece.rotation;
-        self.state.piece.rotation = self.state.piece.rotation.next();
+        self.state.piece.rotation = rotation;
         self.state.piece.rotated = self.get_rotated_shape();
 
-        for &offset in &offsets {
-            self.state.piece.position.0 = original_x + offset;
+        // Take the first kick that fits
+        let kicks = self
+            .rules
+            .rotation
+            .kicks(self.state.piece.typ, original_rotation, rotation);
+        for (kick, (dx, dy)) in kicks.into_iter().enumerate() {
+            self.state.piece.position = (original_position.0 + dx, original_position.1 + dy);
             if self.is_valid_position() {
+                // The new shape snaps into place instead of sliding
+                self.state.piece.previous_position = self.state.piece.position;
+                self.state.piece.last_kick = Some(kick);
+                self.state.piece.last_flip = rotation == original_rotation.flip();
+                self.on_piece_moved();
+                self.events.push(GameEvent::PieceRotated);
+                if self.state.piece.position.1 > self.state.timing.lowest_row {
+                    self.on_piece_fell();
+                }
                 return;
             }
         }
 
         // Restore original position and rotation if no valid position found
-        self.state.piece.position.0 = original_x;
-        self.state.piece.rotation = temp_rotation;
+        self.state.piece.position = original_position;
+        self.state.piece.rotation = original_rotation;
         self.state.piece.rotated = self.get_rotated_shape();
     }
 
-    fn is_valid_position(&self) -> bool {
-        for &(x, y) in &self.state.piece.rotated {
-            let new_x = self.state.piece.position.0 + x;
-            let new_y = self.state.piece.position.1 + y;
-            if new_x < 0
-                || new_x >= BOARD.width
-                || new_y >= BOARD.height
-                || (new_y >= 0 && self.state.board.cells[new_y as usize][new_x as usize].is_some())
-            {
-                return false;
+    // A successful shift or rotation while the lock delay runs
+    fn on_piece_moved(&mut self) {
+        let timing = &mut self.state.timing;
+        let lock = &self.rules.lock;
+        if lock.reset_mode == LockResetMode::Move
+            && timing.lock_timer > 0.0
+            && timing.lock_resets < lock.max_resets
+        {
+            timing.lock_timer = 0.0;
+            timing.lock_resets += 1;
+        }
+    }
+
+    // The piece moved down a row
+    fn on_piece_fell(&mut self) {
+        let timing = &mut self.state.timing;
+        let new_lowest = self.state.piece.position.1 > timing.lowest_row;
+        if new_lowest {
+            timing.lowest_row = self.state.piece.position.1;
+        }
+
+        match self.rules.lock.reset_mode {
+            LockResetMode::Move if new_lowest => {
+                timing.lock_timer = 0.0;
+                timing.lock_resets = 0;
             }
+            LockResetMode::Step => timing.lock_timer = 0.0,
+            _ => {}
         }
-        true
     }
 
-    fn restart(&mut self) {
-        let high_score = self.state.score.highest;
-        let mut new_state = GameState::new();
-        self.renderer.mark_board_dirty();
-        new_state.score.highest = high_score;
-        self.state = new_state;
+    fn is_valid_position(&self) -> bool {
+        self.can_move(0, 0)
     }
 
     fn remove_flashing_lines(&mut self) {
-        let mut new_board = [[None; BOARD.width as usize]; BOARD.height as usize];
-        let mut new_row = BOARD.height as usize - 1;
-
-        // Copy the board, skipping the lines that were cleared
-        for y in (0..BOARD.height as u8).rev() {
-            if !self.state.board.flashing_lines.contains(&y) {
-                new_board[new_row] = self.state.board.cells[y as usize];
-                new_row = new_row.saturating_sub(1);
+        let board = &mut self.state.board;
+        board.cells.remove_rows(&board.flashing_lines);
+    }
+
+    fn update_level(&mut self) {
+        // Lines of the levels that were skipped by starting higher count as
+        // already cleared
+        let skipped = match self.initial_level() {
+            0 => 0,
+            start => self.rules.lines_required(start - 1),
+        };
+        let level = &mut self.state.level;
+        while level.total_lines_cleared + skipped >= self.rules.lines_required(level.current) {
+            level.current += 1;
+            self.events.push(GameEvent::LevelUp(level.current));
+        }
+    }
+
+    // Gravity of the game mode, or of the ruleset at the current level
+    fn fall_interval(&self) -> f32 {
+        self.mode
+            .fall_interval()
+            .unwrap_or_else(|| self.rules.fall_interval(self.state.level.current))
+    }
+
+    fn level_config(&self) -> &LevelConfig {
+        self.rules.level(self.state.level.current)
+    }
+
+    fn add_score(&mut self, points: u32) {
+        self.state.score.current += points;
+        self.state.score.highest = self.state.score.highest.max(self.state.score.current);
+    }
+
+    fn calculate_score(&self, clear: LineClear) -> u32 {
+        let base_score = match (clear.kind, clear.lines) {
+            (ClearKind::Normal, 1) => self.rules.score.single,
+            (ClearKind::Normal, 2) => self.rules.score.double,
+            (ClearKind::Normal, 3) => self.rules.score.triple,
+            (ClearKind::Normal, 4) => self.rules.score.tetris,
+            (ClearKind::TSpinMini, 0) => self.rules.score.tspin_mini,
+            (ClearKind::TSpinMini, 1) => self.rules.score.tspin_mini_single,
+            (ClearKind::TSpinMini, _) => self.rules.score.tspin_mini_double,
+            (ClearKind::TSpin, 0) => self.rules.score.tspin,
+            (ClearKind::TSpin, 1) => self.rules.score.tspin_single,
+            (ClearKind::TSpin, 2) => self.rules.score.tspin_double,
+            (ClearKind::TSpin, _) => self.rules.score.tspin_triple,
+            _ => 0,
+        };
+        let mut score = base_score as f32;
+
+        if clear.back_to_back {
+            score *= self.rules.score.back_to_back_multiplier;
+        }
+
+        if clear.lines > 0 {
+            let combo = self.state.combo.combo.unwrap_or(0);
+            score += (self.rules.score.combo * combo) as f32;
+        }
+
+        if clear.perfect_clear {
+            score += if clear.lines == 4 && clear.back_to_back {
+                self.rules.score.perfect_clear_back_to_back_tetris
+            } else {
+                self.rules.score.perfect_clear[clear.lines.clamp(1, 4) as usize - 1]
+            } as f32;
+        }
+
+        (score * self.level_config().score_multiplier) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SCORE, ZEN};
+
+    const TICK: f32 = 1.0 / 60.0;
+
+    fn started_game(seed: u64) -> Game {
+        let mut game = Game::new(seed, Ruleset::default());
+        game.start();
+        game
+    }
+
+    fn board_cells(game: &Game) -> Vec<Option<Cell>> {
+        let board = &game.state.board.cells;
+        (board.top()..board.height)
+            .flat_map(|y| board.row(y).to_vec())
+            .collect()
+    }
+
+    // Cells the falling piece will take once it lands
+    fn landing_cells(game: &Game) -> Vec<(i32, i32)> {
+        let piece = &game.state.piece;
+        let distance = game.state.drop_distance();
+        piece
+            .rotated
+            .iter()
+            .map(|&(x, y)| (piece.position.0 + x, piece.position.1 + y + distance))
+            .collect()
+    }
+
+    // Empties the board and fills the bottom rows from a picture, `#` for
+    // garbage and `.` for empty
+    fn fill_rows(game: &mut Game, rows: &[&str]) {
+        let board = &mut game.state.board.cells;
+        board.clear_above(board.height);
+        let top = board.height - rows.len() as i32;
+        for (y, row) in (top..).zip(rows) {
+            for (x, cell) in (0..).zip(row.chars()) {
+                board.set(x, y, (cell == '#').then_some(Cell::Garbage));
             }
         }
+    }
 
-        self.renderer.mark_board_dirty();
-        self.state.board.cells = new_board;
+    fn set_piece(game: &mut Game, typ: Tetromino, rotation: RotationState, position: (i32, i32)) {
+        let piece = &mut game.state.piece;
+        piece.typ = typ;
+        piece.rotation = rotation;
+        piece.rotated = typ.cells(rotation);
+        piece.position = position;
     }
 
-    fn update_level(&mut self) {
-        let current_level = self.state.level.current;
-        let current_config = &LEVEL_CONFIGS[current_level];
+    // Bottom rows with a well in column 0, topped by a few leftover blocks
+    const TETRIS_WELL: [&str; 5] = [
+        "........##",
+        ".#########",
+        ".#########",
+        ".#########",
+        ".#########",
+    ];
+    const SINGLE_WELL: [&str; 4] = ["........##", "..........", "..........", ".#########"];
 
-        if self.state.level.total_lines_cleared >= current_config.lines_required
-            && current_level < LEVEL_CONFIGS.len() - 1
-        {
-            let next_level = current_level + 1;
-            self.state.level.current = next_level;
+    // Drops a vertical I into the well, waits out the line clear and returns
+    // the points it scored
+    fn drop_into_well(game: &mut Game, rows: &[&str]) -> u32 {
+        fill_rows(game, rows);
+        set_piece(game, Tetromino::I, RotationState::Right, (-2, 16));
+        let score = game.state.score.current;
+        game.tick(TICK, &[InputState::HardDrop]);
+        let points = game.state.score.current - score;
+        let ticks = (TIMING.line_clearing / TICK).ceil() as usize;
+        for _ in 0..ticks {
+            game.tick(TICK, &[]);
         }
+        points
     }
 
-    fn calculate_score(&self, lines_cleared: u32) -> u32 {
-        let base_score = match lines_cleared {
-            1 => SCORE.single,
-            2 => SCORE.double,
-            3 => SCORE.triple,
-            4 => SCORE.tetris,
-            _ => 0,
+    #[test]
+    fn same_seed_and_inputs_give_the_same_game() {
+        let script = [
+            InputState::MoveLeft,
+            InputState::RotateCw,
+            InputState::HardDrop,
+            InputState::MoveRight,
+            InputState::MoveRight,
+            InputState::Hold,
+            InputState::RotateCcw,
+            InputState::SoftDrop,
+            InputState::HardDrop,
+            InputState::None,
+        ];
+        let play = || {
+            let mut game = started_game(42);
+            for tick in 0..2000 {
+                game.tick(TICK, &[script[tick % script.len()]]);
+            }
+            game
         };
 
-        (base_score as f32 * LEVEL_CONFIGS[self.state.level.current].score_multiplier) as u32
+        let (first, second) = (play(), play());
+        assert!(first.state.pieces_placed > 0);
+        assert_eq!(board_cells(&first), board_cells(&second));
+        assert_eq!(first.state.score.current, second.state.score.current);
+        assert_eq!(first.state.pieces_placed, second.state.pieces_placed);
+        assert_eq!(first.state.status, second.state.status);
+    }
+
+    #[test]
+    fn hard_drop_locks_the_piece_where_it_lands() {
+        let mut game = started_game(7);
+        let typ = game.state.piece.typ;
+        let cells = landing_cells(&game);
+        game.take_events();
+
+        game.tick(TICK, &[InputState::HardDrop]);
+
+        for (x, y) in cells {
+            assert_eq!(game.state.board.cells.get(x, y), Some(Cell::Piece(typ)));
+        }
+        assert_eq!(game.state.pieces_placed, 1);
+        let events = game.take_events();
+        assert!(events.contains(&GameEvent::PieceLocked));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, GameEvent::PieceSpawned(_))));
+    }
+
+    #[test]
+    fn cleared_lines_collapse_before_the_next_piece_enters() {
+        let mut game = started_game(7);
+
+        // A well in column 0 of the bottom four rows, and a stack over the
+        // spawn columns reaching into the hidden rows
+        let board = &mut game.state.board.cells;
+        for y in 16..20 {
+            for x in 1..10 {
+                board.set(x, y, Some(Cell::Garbage));
+            }
+        }
+        for y in -3..16 {
+            for x in 3..7 {
+                board.set(x, y, Some(Cell::Garbage));
+            }
+        }
+
+        // A vertical I above the well
+        set_piece(&mut game, Tetromino::I, RotationState::Right, (-2, 0));
+
+        game.tick(TICK, &[InputState::HardDrop]);
+        assert_eq!(game.state.board.flashing_lines.len(), 4);
+        let ticks = (TIMING.line_clearing / TICK).ceil() as usize;
+        for _ in 0..ticks {
+            game.tick(TICK, &[]);
+        }
+
+        assert_eq!(game.state.status, GameStatus::Playing);
+        assert!(game.state.board.flashing_lines.is_empty());
+        assert!(game.state.board.cells.is_row_empty(-1));
+    }
+
+    #[test]
+    fn t_spin_double_with_both_front_corners_blocked() {
+        let mut game = started_game(7);
+        fill_rows(&mut game, &["####......", "###...####", "####.#####"]);
+        set_piece(&mut game, Tetromino::T, RotationState::Right, (3, 17));
+
+        // Rotating into the slot points the T down at the two filled bottom corners
+        game.tick(TICK, &[InputState::RotateCw, InputState::HardDrop]);
+
+        let clear = game.state.score.last_clear.unwrap();
+        assert_eq!(clear.lines, 2);
+        assert_eq!(clear.kind, ClearKind::TSpin);
+    }
+
+    #[test]
+    fn t_spin_mini_with_one_front_corner_blocked() {
+        let mut game = started_game(7);
+        fill_rows(&mut game, &["...#.#....", "###...####", "####..####"]);
+        set_piece(&mut game, Tetromino::T, RotationState::Zero, (3, 17));
+
+        game.tick(TICK, &[InputState::RotateCw, InputState::HardDrop]);
+
+        let clear = game.state.score.last_clear.unwrap();
+        assert_eq!(clear.lines, 0);
+        assert_eq!(clear.kind, ClearKind::TSpinMini);
+    }
+
+    #[test]
+    fn last_kick_upgrades_a_mini_to_a_t_spin() {
+        let mut game = started_game(7);
+        fill_rows(
+            &mut game,
+            &[
+                "....#.....",
+                "####...###",
+                "####.#####",
+                "####..####",
+                "####..####",
+            ],
+        );
+        set_piece(&mut game, Tetromino::T, RotationState::Zero, (4, 15));
+
+        // Only the last kick fits, two rows down and one to the left
+        game.tick(TICK, &[InputState::RotateCw]);
+        assert_eq!(game.state.piece.position, (3, 17));
+        assert_eq!(game.state.piece.last_kick, Some(4));
+
+        game.tick(TICK, &[InputState::HardDrop]);
+        let clear = game.state.score.last_clear.unwrap();
+        assert_eq!(clear.lines, 2);
+        assert_eq!(clear.kind, ClearKind::TSpin);
+    }
+
+    #[test]
+    fn last_kick_of_a_180_rotation_stays_a_mini() {
+        let mut game = started_game(7);
+        fill_rows(
+            &mut game,
+            &[
+                "...#.#....",
+                "###...####",
+                "####..####",
+                "###..#####",
+                "####.#####",
+            ],
+        );
+        set_piece(&mut game, Tetromino::T, RotationState::Left, (3, 17));
+
+        game.tick(TICK, &[InputState::Rotate180]);
+        assert_eq!(game.state.piece.position, (3, 15));
+        assert_eq!(game.state.piece.last_kick, Some(4));
+
+        // The piece would fall on, so check where it stands after the kick
+        assert_eq!(game.detect_spin(), ClearKind::TSpinMini);
+    }
+
+    #[test]
+    fn combo_counts_clearing_placements_in_a_row() {
+        let mut game = started_game(7);
+        assert_eq!(drop_into_well(&mut game, &SINGLE_WELL), SCORE.single);
+        assert_eq!(
+            drop_into_well(&mut game, &SINGLE_WELL),
+            SCORE.single + SCORE.combo
+        );
+        assert_eq!(
+            drop_into_well(&mut game, &SINGLE_WELL),
+            SCORE.single + 2 * SCORE.combo
+        );
+        assert_eq!(game.state.combo.combo, Some(2));
+
+        // A placement without lines ends the combo
+        assert_eq!(drop_into_well(&mut game, &SINGLE_WELL[..1]), 0);
+        assert_eq!(game.state.combo.combo, None);
+        assert_eq!(drop_into_well(&mut game, &SINGLE_WELL), SCORE.single);
+    }
+
+    #[test]
+    fn tetrises_in_a_row_score_back_to_back() {
+        let back_to_back_tetris = (SCORE.tetris as f32 * SCORE.back_to_back_multiplier) as u32;
+
+        let mut game = started_game(7);
+        assert_eq!(drop_into_well(&mut game, &TETRIS_WELL), SCORE.tetris);
+        assert!(!game.state.score.last_clear.unwrap().back_to_back);
+        assert_eq!(
+            drop_into_well(&mut game, &TETRIS_WELL),
+            back_to_back_tetris + SCORE.combo
+        );
+        assert!(game.state.score.last_clear.unwrap().back_to_back);
+        assert_eq!(game.state.combo.back_to_back, 2);
+
+        // A single in between breaks the chain
+        let mut game = started_game(7);
+        drop_into_well(&mut game, &TETRIS_WELL);
+        drop_into_well(&mut game, &SINGLE_WELL);
+        assert_eq!(game.state.combo.back_to_back, 0);
+        assert_eq!(
+            drop_into_well(&mut game, &TETRIS_WELL),
+            SCORE.tetris + 2 * SCORE.combo
+        );
+        assert!(!game.state.score.last_clear.unwrap().back_to_back);
+        assert_eq!(game.state.combo.back_to_back, 1);
+    }
+
+    #[test]
+    fn t_spin_without_lines_keeps_back_to_back() {
+        let mut game = started_game(7);
+        drop_into_well(&mut game, &TETRIS_WELL);
+
+        fill_rows(&mut game, &["...#.#....", "###...####", "####..####"]);
+        set_piece(&mut game, Tetromino::T, RotationState::Zero, (3, 17));
+        game.tick(TICK, &[InputState::RotateCw, InputState::HardDrop]);
+        assert_eq!(
+            game.state.score.last_clear.unwrap().kind,
+            ClearKind::TSpinMini
+        );
+        assert_eq!(game.state.combo.back_to_back, 1);
+
+        let back_to_back_tetris = (SCORE.tetris as f32 * SCORE.back_to_back_multiplier) as u32;
+        assert_eq!(drop_into_well(&mut game, &TETRIS_WELL), back_to_back_tetris);
+        assert!(game.state.score.last_clear.unwrap().back_to_back);
+    }
+
+    #[test]
+    fn perfect_clear_adds_its_bonus() {
+        let mut game = started_game(7);
+        let points = drop_into_well(&mut game, &TETRIS_WELL[1..]);
+
+        assert!(game.state.score.last_clear.unwrap().perfect_clear);
+        assert_eq!(points, SCORE.tetris + SCORE.perfect_clear[3]);
+        let board = &game.state.board.cells;
+        assert!((board.top()..board.height).all(|y| board.is_row_empty(y)));
+    }
+
+    #[test]
+    fn zen_soft_drop_keeps_its_pace_at_high_levels() {
+        for gravity in [true, false] {
+            let mut game = Game::new(7, Ruleset::default());
+            game.set_mode(GameMode::Zen { gravity });
+            game.start();
+            // Past level 19 the guideline drops pieces instantly
+            game.state.level.current = 25;
+
+            // One row per soft drop interval, not all the way down
+            let start = game.state.piece.position.1;
+            let interval = ZEN.fall_interval / game.soft_drop_factor;
+            let ticks = (interval / TICK) as usize;
+            for _ in 0..ticks - 1 {
+                game.tick(TICK, &[InputState::SoftDrop]);
+            }
+            assert_eq!(game.state.piece.position.1, start);
+            for _ in 0..2 {
+                game.tick(TICK, &[InputState::SoftDrop]);
+            }
+            assert_eq!(game.state.piece.position.1, start + 1);
+        }
+    }
+
+    // Puts the falling piece on the floor, as if it had fallen there
+    fn ground_piece(game: &mut Game) {
+        game.state.piece.position.1 += game.state.drop_distance();
+        game.state.timing.lowest_row = game.state.piece.position.1;
+    }
+
+    #[test]
+    fn piece_locks_after_the_lock_delay() {
+        let mut game = started_game(7);
+        ground_piece(&mut game);
+        // A step that adds up to the lock delay without rounding
+        let lock_delay = game.rules().level(0).lock_delay;
+        let delta = lock_delay / 32.0;
+
+        for _ in 0..31 {
+            game.tick(delta, &[]);
+        }
+        assert_eq!(game.state.pieces_placed, 0);
+
+        game.tick(delta, &[]);
+        assert_eq!(game.state.pieces_placed, 1);
+    }
+
+    #[test]
+    fn piece_locks_at_once_when_its_move_resets_run_out() {
+        let mut game = started_game(7);
+        ground_piece(&mut game);
+        let max_resets = game.rules().lock.max_resets;
+        assert_eq!(game.rules().lock.reset_mode, LockResetMode::Move);
+
+        // Start the lock delay, then shuffle the piece to reset it
+        game.tick(TICK, &[]);
+        for reset in 1..max_resets {
+            let input = if reset % 2 == 1 {
+                InputState::MoveLeft
+            } else {
+                InputState::MoveRight
+            };
+            game.tick(TICK, &[input]);
+            assert_eq!(game.state.timing.lock_resets, reset);
+            assert_eq!(game.state.pieces_placed, 0);
+        }
+
+        game.tick(TICK, &[InputState::MoveLeft]);
+        assert_eq!(game.state.pieces_placed, 1);
     }
 }
@@ -0,0 +1,28 @@
+use crate::{
+    rng::Rng,
+    state::{Board, Cell},
+};
+
+// Pushes a row of garbage in from the bottom. It is full apart from one hole,
+// which never sits straight above the hole of the garbage row below it.
+// Returns false when blocks were pushed off the top of the board.
+pub fn add_row(board: &mut Board, rng: &mut Rng) -> bool {
+    let below = board.row(board.height - 1);
+    let hole_below = if below.contains(&Some(Cell::Garbage)) {
+        below.iter().position(Option::is_none)
+    } else {
+        None
+    };
+
+    // Pick among the columns left over, skipping the one below
+    let choices = board.width as usize - hole_below.is_some() as usize;
+    let mut hole = rng.gen_range(0, choices as i32) as usize;
+    if hole_below.is_some_and(|below| hole >= below) {
+        hole += 1;
+    }
+
+    let row = (0..board.width as usize)
+        .map(|x| (x != hole).then_some(Cell::Garbage))
+        .collect();
+    board.push_row(row)
+}
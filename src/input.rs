@@ -1,27 +1,49 @@
 use macroquad::prelude::*;
+use smallvec::SmallVec;
 
-use crate::config::{Time, INPUT};
+use crate::{
+    config::{Time, INPUT},
+    settings::Settings,
+};
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum InputState {
     None,
     MoveLeft,
     MoveRight,
-    Rotate,
-    Drop,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    SoftDrop,
+    HardDrop,
+    Hold,
+    // Handled by the frontend, the game core ignores it
+    Pause,
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct TouchPosition {
     x: f32,
+    y: f32,
+}
+
+// A held direction key, counting the repeats it has produced so far
+#[derive(Copy, Clone)]
+struct AutoShift {
+    key: KeyCode,
+    direction: InputState,
+    start: Time,
+    repeats: u32,
 }
 
 pub struct InputHandler {
     touch_start: Option<(TouchPosition, Time)>,
     last_move_time: Time,
-    key_hold_start: Option<(KeyCode, Time)>,
+    auto_shift: Option<AutoShift>,
+    soft_drop_blocked: bool,
     is_moving: bool,
     is_dropping: bool,
+    is_swiped: bool,
     move_direction: Option<InputState>,
 }
 
@@ -30,76 +52,112 @@ impl InputHandler {
         Self {
             touch_start: None,
             last_move_time: Time(0.0),
-            key_hold_start: None,
+            auto_shift: None,
+            soft_drop_blocked: false,
             is_moving: false,
             is_dropping: false,
+            is_swiped: false,
             move_direction: None,
         }
     }
 
-    pub fn update(&mut self) -> InputState {
+    // `board_width` caps how far an instant auto shift (ARR 0) travels
+    pub fn update(&mut self, settings: &Settings, board_width: i32) -> SmallVec<[InputState; 4]> {
+        let mut inputs = SmallVec::new();
+
         let touch_input = self.handle_touch();
         if touch_input != InputState::None {
-            return touch_input;
+            inputs.push(touch_input);
         }
 
-        let keyboard_input = self.handle_keyboard();
-        if keyboard_input != InputState::None {
-            return keyboard_input;
-        }
-
-        InputState::None
+        self.handle_keyboard(settings, board_width, &mut inputs);
+        inputs
     }
 
-    fn handle_keyboard(&mut self) -> InputState {
+    fn handle_keyboard(
+        &mut self,
+        settings: &Settings,
+        board_width: i32,
+        inputs: &mut SmallVec<[InputState; 4]>,
+    ) {
         let current_time = Time(get_time());
 
         // Check for key press
         for key in [
             KeyCode::Left,
             KeyCode::Right,
-            KeyCode::Down,
             KeyCode::Up,
             KeyCode::A,
             KeyCode::D,
-            KeyCode::S,
             KeyCode::W,
+            KeyCode::X,
+            KeyCode::Z,
+            KeyCode::Q,
+            KeyCode::E,
+            KeyCode::Space,
+            KeyCode::C,
+            KeyCode::LeftShift,
+            KeyCode::RightShift,
+            KeyCode::Escape,
+            KeyCode::P,
         ] {
             if is_key_pressed(key) {
-                self.key_hold_start = Some((key, current_time));
-                match key {
-                    KeyCode::Left | KeyCode::A => return InputState::MoveLeft,
-                    KeyCode::Right | KeyCode::D => return InputState::MoveRight,
-                    KeyCode::Up | KeyCode::W => return InputState::Rotate,
-                    _ => (),
+                let input = match key {
+                    KeyCode::Left | KeyCode::A => InputState::MoveLeft,
+                    KeyCode::Right | KeyCode::D => InputState::MoveRight,
+                    KeyCode::Up | KeyCode::W | KeyCode::X => InputState::RotateCw,
+                    KeyCode::Z | KeyCode::Q => InputState::RotateCcw,
+                    KeyCode::E => InputState::Rotate180,
+                    KeyCode::Space => InputState::HardDrop,
+                    KeyCode::Escape | KeyCode::P => InputState::Pause,
+                    _ => InputState::Hold,
+                };
+
+                // The last pressed direction takes over auto shift
+                if matches!(input, InputState::MoveLeft | InputState::MoveRight) {
+                    self.auto_shift = Some(AutoShift {
+                        key,
+                        direction: input,
+                        start: current_time,
+                        repeats: 0,
+                    });
                 }
+                inputs.push(input);
             }
         }
 
-        // Check for held keys
-        if let Some((key, start_time)) = self.key_hold_start {
-            if is_key_down(key) {
-                if current_time - start_time > INPUT.hold_threshold {
-                    let elapsed = current_time - self.last_move_time;
-                    match key {
-                        KeyCode::Left | KeyCode::A if elapsed > INPUT.move_cooldown => {
-                            self.last_move_time = current_time;
-                            return InputState::MoveLeft;
-                        }
-                        KeyCode::Right | KeyCode::D if elapsed > INPUT.move_cooldown => {
-                            self.last_move_time = current_time;
-                            return InputState::MoveRight;
-                        }
-                        KeyCode::Down | KeyCode::S => return InputState::Drop,
-                        _ => (),
+        // Repeat held directions once DAS has charged, catching up on every
+        // repeat that fell into this frame
+        if let Some(shift) = &mut self.auto_shift {
+            if is_key_down(shift.key) {
+                let held = (current_time - shift.start).0;
+                let das = settings.das_ms as f64 / 1000.0;
+                if held >= das {
+                    let due = if settings.arr_ms == 0 {
+                        shift.repeats + board_width as u32
+                    } else {
+                        let arr = settings.arr_ms as f64 / 1000.0;
+                        ((held - das) / arr) as u32 + 1
+                    };
+                    let due = due.min(shift.repeats + board_width as u32);
+                    for _ in shift.repeats..due {
+                        inputs.push(shift.direction);
                     }
+                    shift.repeats = due;
                 }
             } else {
-                self.key_hold_start = None;
+                self.auto_shift = None;
             }
         }
 
-        InputState::None
+        // Soft drop while held, but only after a fresh press once a piece locked
+        if is_key_down(KeyCode::Down) || is_key_down(KeyCode::S) {
+            if !self.soft_drop_blocked {
+                inputs.push(InputState::SoftDrop);
+            }
+        } else {
+            self.soft_drop_blocked = false;
+        }
     }
 
     fn handle_touch(&mut self) -> InputState {
@@ -110,22 +168,51 @@ impl InputHandler {
             return InputState::None;
         }
 
+        // A second finger pauses, and the rest of the touch does nothing
+        if touches.len() > 1
+            && touches
+                .iter()
+                .any(|touch| touch.phase == TouchPhase::Started)
+            && !self.is_swiped
+        {
+            self.is_swiped = true;
+            self.is_dropping = false;
+            return InputState::Pause;
+        }
+
         let touch = &touches[0];
         match touch.phase {
             TouchPhase::Started => {
                 self.touch_start = Some((
                     TouchPosition {
                         x: touch.position.x,
+                        y: touch.position.y,
                     },
                     current_time,
                 ));
             }
             TouchPhase::Moved => {
                 if self.is_dropping {
-                    return InputState::Drop;
+                    return InputState::SoftDrop;
                 }
                 if let Some((start_pos, _)) = self.touch_start {
                     let dx = touch.position.x - start_pos.x;
+                    let dy = touch.position.y - start_pos.y;
+
+                    // Vertical swipes trigger once per touch: up turns the
+                    // piece around, down hard drops it
+                    if !self.is_moving && dy.abs() > INPUT.swipe_threshold && dy.abs() > dx.abs() {
+                        if self.is_swiped {
+                            return InputState::None;
+                        }
+                        self.is_swiped = true;
+                        return if dy < 0.0 {
+                            InputState::Rotate180
+                        } else {
+                            InputState::HardDrop
+                        };
+                    }
+
                     if dx.abs() > INPUT.swipe_threshold {
                         let elapsed = current_time - self.last_move_time;
                         if elapsed > INPUT.move_cooldown_swipe {
@@ -152,17 +239,28 @@ impl InputHandler {
                     }
                 } else if let Some((_, start_time)) = self.touch_start {
                     let touch_duration = current_time - start_time;
-                    if touch_duration > INPUT.hold_threshold {
+                    if touch_duration > INPUT.hold_threshold && !self.is_swiped {
                         self.is_dropping = true;
-                        return InputState::Drop;
+                        return InputState::SoftDrop;
                     }
                 }
             }
             TouchPhase::Ended | TouchPhase::Cancelled => {
-                if let Some((_, start_time)) = self.touch_start {
+                if let Some((start_pos, start_time)) = self.touch_start {
                     let touch_duration = current_time - start_time;
-                    if touch_duration < INPUT.touch_threshold && !self.is_moving {
-                        return InputState::Rotate;
+                    if touch_duration < INPUT.touch_threshold && !self.is_moving && !self.is_swiped
+                    {
+                        // Tap the top of the screen, where the hold slot is, to hold
+                        if start_pos.y < screen_height() * INPUT.hold_zone {
+                            return InputState::Hold;
+                        }
+
+                        // Tap the left half to turn left, the right half to turn right
+                        return if start_pos.x < screen_width() / 2.0 {
+                            InputState::RotateCcw
+                        } else {
+                            InputState::RotateCw
+                        };
                     }
                 }
                 self.touch_start = None;
@@ -176,12 +274,13 @@ impl InputHandler {
     fn reset_movement(&mut self) {
         self.is_moving = false;
         self.is_dropping = false;
+        self.is_swiped = false;
         self.move_direction = None;
     }
 
     pub fn reset(&mut self) {
         self.touch_start = None;
-        self.key_hold_start = None;
+        self.soft_drop_blocked = true;
         self.reset_movement();
     }
 }
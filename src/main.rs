@@ -1,18 +1,28 @@
+mod app;
 mod cache;
 mod config;
 mod dummy_board;
+mod event;
+mod focus;
 mod game;
+mod garbage;
 mod input;
+mod mode;
+mod randomizer;
 mod renderer;
+mod rng;
+mod ruleset;
 mod screen;
+mod settings;
 mod state;
 mod storage;
 mod tetromino;
 
+use app::App;
 use config::TEXT;
-use game::Game;
 use macroquad::prelude::*;
 use miniquad::date;
+use ruleset::{Ruleset, PRESETS};
 
 fn window_conf() -> Conf {
     Conf {
@@ -23,17 +33,36 @@ fn window_conf() -> Conf {
     }
 }
 
+fn arg_value(prefix: &str) -> Option<String> {
+    std::env::args().find_map(|arg| arg.strip_prefix(prefix).map(str::to_string))
+}
+
 #[macroquad::main(window_conf)]
 async fn main() {
-    // Set seed for random generator
-    rand::srand(date::now() as u64);
+    let mut rulesets = Ruleset::presets();
+    let mut selected = 0;
+
+    // Optional `--ruleset=<guideline|nes|tgm|path to .toml/.json>` argument
+    if let Some(arg) = arg_value("--ruleset=") {
+        match PRESETS
+            .iter()
+            .position(|(name, _)| name.eq_ignore_ascii_case(&arg))
+        {
+            Some(index) => selected = index,
+            None => match Ruleset::from_file(&arg) {
+                Ok(rules) => {
+                    rulesets.push(rules);
+                    selected = rulesets.len() - 1;
+                }
+                Err(e) => eprintln!("Could not load ruleset {e}"),
+            },
+        }
+    }
 
-    let mut game = Game::new();
+    let mut app = App::new(date::now() as u64, rulesets, selected);
     loop {
-        let input_state = game.input.update();
-        game.handle_input(input_state);
-        game.update_logic();
-        game.renderer.draw(&game.state);
+        app.update();
+        app.draw();
         next_frame().await;
     }
 }
@@ -0,0 +1,172 @@
+use crate::{
+    config::{TopOutPolicy, DIG, MARATHON, ZEN},
+    ruleset::Ruleset,
+};
+
+// What a game is played for. Modes are picked on the start screen.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameMode {
+    // Clear the lines, or play until topping out without a goal
+    Marathon { goal: Option<u32> },
+    // Clear the lines as fast as possible
+    Sprint { lines: u32 },
+    // Score as much as possible before the time runs out
+    Ultra { seconds: u32 },
+    // Relaxed play that never ends, with slow or no gravity
+    Zen { gravity: bool },
+    // Clear away the garbage rows as fast as possible, optionally with more
+    // rising from below
+    Dig { rows: u32, rising: bool },
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 13] = [
+        GameMode::Marathon {
+            goal: Some(MARATHON.goal_lines),
+        },
+        GameMode::Marathon { goal: None },
+        GameMode::Sprint { lines: 20 },
+        GameMode::Sprint { lines: 40 },
+        GameMode::Sprint { lines: 100 },
+        GameMode::Ultra { seconds: 120 },
+        GameMode::Ultra { seconds: 180 },
+        GameMode::Ultra { seconds: 300 },
+        GameMode::Zen { gravity: true },
+        GameMode::Zen { gravity: false },
+        GameMode::Dig {
+            rows: 10,
+            rising: false,
+        },
+        GameMode::Dig {
+            rows: 18,
+            rising: false,
+        },
+        GameMode::Dig {
+            rows: 10,
+            rising: true,
+        },
+    ];
+
+    // Name of the mode and its variant, for storage keys
+    fn key(&self) -> String {
+        match self {
+            GameMode::Marathon { goal: None } => "marathon".to_string(),
+            GameMode::Marathon { goal: Some(lines) } => format!("marathon_{lines}"),
+            GameMode::Sprint { lines } => format!("sprint_{lines}"),
+            GameMode::Ultra { seconds } => format!("ultra_{seconds}"),
+            GameMode::Zen { gravity: true } => "zen".to_string(),
+            GameMode::Zen { gravity: false } => "zen_no_gravity".to_string(),
+            GameMode::Dig {
+                rows,
+                rising: false,
+            } => format!("dig_{rows}"),
+            GameMode::Dig { rows, rising: true } => format!("dig_{rows}_rising"),
+        }
+    }
+
+    // Whether the mode races the clock and keeps a personal best time
+    pub fn has_best_time(&self) -> bool {
+        matches!(self, GameMode::Sprint { .. } | GameMode::Dig { .. })
+    }
+
+    // Storage key for the personal best time, for modes that keep one.
+    // Records are kept per ruleset, whose gravity and scoring differ.
+    pub fn record_key(&self, rules: &Ruleset) -> Option<String> {
+        self.has_best_time()
+            .then(|| format!("{}_{}", self.key(), rules.key()))
+    }
+
+    // Storage key for the high score, per ruleset as well. Endless Marathon
+    // with the guideline rules keeps the key from before there were modes,
+    // so old high scores carry over.
+    pub fn high_score_key(&self, rules: &Ruleset) -> String {
+        if *self == (GameMode::Marathon { goal: None }) && rules.key() == "guideline" {
+            return "high_score".to_string();
+        }
+        format!("high_score_{}_{}", self.key(), rules.key())
+    }
+
+    // Lines that end the game
+    pub fn line_goal(&self) -> Option<u32> {
+        match self {
+            GameMode::Marathon { goal } => *goal,
+            GameMode::Sprint { lines } => Some(*lines),
+            GameMode::Ultra { .. } | GameMode::Zen { .. } | GameMode::Dig { .. } => None,
+        }
+    }
+
+    // Seconds after which the game ends
+    pub fn time_limit(&self) -> Option<f64> {
+        match self {
+            GameMode::Ultra { seconds } => Some(*seconds as f64),
+            GameMode::Marathon { .. }
+            | GameMode::Sprint { .. }
+            | GameMode::Zen { .. }
+            | GameMode::Dig { .. } => None,
+        }
+    }
+
+    // Seconds per row when the mode overrides the ruleset's gravity
+    pub fn fall_interval(&self) -> Option<f32> {
+        match self {
+            GameMode::Zen { gravity: true } => Some(ZEN.fall_interval),
+            // Never falls on its own, only soft and hard drops move it down
+            GameMode::Zen { gravity: false } => Some(f32::MAX),
+            GameMode::Marathon { .. }
+            | GameMode::Sprint { .. }
+            | GameMode::Ultra { .. }
+            | GameMode::Dig { .. } => None,
+        }
+    }
+
+    // Seconds per row that soft drop speeds up when the mode sets its own
+    // pace, so it does not follow the ruleset's gravity into instant drops
+    pub fn soft_drop_base(&self) -> Option<f32> {
+        match self {
+            // Without gravity soft drop keeps the pace of Zen with gravity
+            GameMode::Zen { .. } => Some(ZEN.fall_interval),
+            GameMode::Marathon { .. }
+            | GameMode::Sprint { .. }
+            | GameMode::Ultra { .. }
+            | GameMode::Dig { .. } => None,
+        }
+    }
+
+    pub fn top_out_policy(&self) -> TopOutPolicy {
+        match self {
+            GameMode::Zen { .. } => TopOutPolicy::ClearUpperHalf,
+            GameMode::Marathon { .. }
+            | GameMode::Sprint { .. }
+            | GameMode::Ultra { .. }
+            | GameMode::Dig { .. } => TopOutPolicy::GameOver,
+        }
+    }
+
+    // Garbage rows on the board at the start
+    pub fn garbage_rows(&self) -> u32 {
+        match self {
+            GameMode::Dig { rows, .. } => *rows,
+            GameMode::Marathon { .. }
+            | GameMode::Sprint { .. }
+            | GameMode::Ultra { .. }
+            | GameMode::Zen { .. } => 0,
+        }
+    }
+
+    // Seconds between garbage rows rising during the game
+    pub fn garbage_interval(&self) -> Option<f32> {
+        match self {
+            GameMode::Dig { rising: true, .. } => Some(DIG.rise_interval),
+            GameMode::Dig { rising: false, .. }
+            | GameMode::Marathon { .. }
+            | GameMode::Sprint { .. }
+            | GameMode::Ultra { .. }
+            | GameMode::Zen { .. } => None,
+        }
+    }
+
+    // Whether the player may pick a higher level to start on
+    pub fn has_start_level(&self) -> bool {
+        matches!(self, GameMode::Marathon { .. })
+    }
+}
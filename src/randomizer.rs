@@ -0,0 +1,137 @@
+use serde::Deserialize;
+use smallvec::SmallVec;
+
+use crate::{rng::Rng, tetromino::Tetromino};
+
+pub trait Randomizer {
+    fn next(&mut self, rng: &mut Rng) -> Tetromino;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RandomizerKind {
+    #[default]
+    #[serde(alias = "7-bag")]
+    Bag,
+    #[serde(alias = "random")]
+    Classic,
+    Nes,
+    #[serde(alias = "tgm")]
+    History,
+}
+
+impl RandomizerKind {
+    pub fn create(&self) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::Bag => Box::new(BagRandomizer::new()),
+            RandomizerKind::Classic => Box::new(ClassicRandomizer),
+            RandomizerKind::Nes => Box::new(NesRandomizer::new()),
+            RandomizerKind::History => Box::new(HistoryRandomizer::new()),
+        }
+    }
+}
+
+// Deals all seven pieces in a shuffled order before refilling
+pub struct BagRandomizer {
+    bag: SmallVec<[Tetromino; 7]>,
+}
+
+impl BagRandomizer {
+    pub fn new() -> Self {
+        Self {
+            bag: SmallVec::new(),
+        }
+    }
+
+    fn refill(&mut self, rng: &mut Rng) {
+        self.bag.extend_from_slice(&Tetromino::ALL);
+
+        // Fisher-Yates shuffle
+        for i in (1..self.bag.len()).rev() {
+            let j = rng.gen_range(0, i as i32 + 1) as usize;
+            self.bag.swap(i, j);
+        }
+    }
+}
+
+impl Randomizer for BagRandomizer {
+    fn next(&mut self, rng: &mut Rng) -> Tetromino {
+        if self.bag.is_empty() {
+            self.refill(rng);
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+// Memoryless, every piece is equally likely every time
+pub struct ClassicRandomizer;
+
+impl Randomizer for ClassicRandomizer {
+    fn next(&mut self, rng: &mut Rng) -> Tetromino {
+        Tetromino::random(rng)
+    }
+}
+
+// NES: roll an eighth "reroll" slot, and reroll once on it or on a repeat
+pub struct NesRandomizer {
+    last: Option<Tetromino>,
+}
+
+impl NesRandomizer {
+    pub fn new() -> Self {
+        Self { last: None }
+    }
+}
+
+impl Randomizer for NesRandomizer {
+    fn next(&mut self, rng: &mut Rng) -> Tetromino {
+        let roll = rng.gen_range(0, Tetromino::ALL.len() as i32 + 1) as usize;
+        let piece = match Tetromino::ALL.get(roll) {
+            Some(&piece) if Some(piece) != self.last => piece,
+            _ => Tetromino::random(rng),
+        };
+        self.last = Some(piece);
+        piece
+    }
+}
+
+// TGM: reroll a few times while the piece is one of the last four dealt
+pub struct HistoryRandomizer {
+    history: [Tetromino; 4],
+    first: bool,
+}
+
+impl HistoryRandomizer {
+    const ROLLS: usize = 6;
+
+    pub fn new() -> Self {
+        Self {
+            history: [Tetromino::Z, Tetromino::S, Tetromino::S, Tetromino::Z],
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for HistoryRandomizer {
+    fn next(&mut self, rng: &mut Rng) -> Tetromino {
+        let piece = if self.first {
+            // Never start with a piece that forces an overhang
+            const FIRST: [Tetromino; 4] = [Tetromino::I, Tetromino::J, Tetromino::L, Tetromino::T];
+            self.first = false;
+            FIRST[rng.gen_range(0, FIRST.len() as i32) as usize]
+        } else {
+            let mut piece = Tetromino::random(rng);
+            for _ in 1..Self::ROLLS {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+                piece = Tetromino::random(rng);
+            }
+            piece
+        };
+
+        self.history.rotate_right(1);
+        self.history[0] = piece;
+        piece
+    }
+}
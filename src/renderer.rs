@@ -1,10 +1,16 @@
 use crate::{
     cache::{FontCache, TextCache},
-    config::{BOARD, TEXT, TIMING},
+    config::{BoardDimensions, TEXT, TIMING},
+    event::GameEvent,
+    game::Game,
+    mode::GameMode,
     screen::ScreenConfig,
-    state::{Board, GameState, GameStatus, PieceState},
+    settings::{Settings, SettingsItem},
+    state::{Board, ClearKind, ComboState, GameStatus, HoldState, LineClear, PieceState},
+    tetromino::Tetromino,
 };
 use macroquad::prelude::*;
+use std::collections::VecDeque;
 
 struct ButtonBounds {
     x: f32,
@@ -22,13 +28,15 @@ pub struct Renderer {
     last_fps_update: f64,
     current_fps: i32,
     board_dirty: bool,
+    // Cleared lines are still on the board
+    clearing: bool,
     flashing: bool,
 }
 
 impl Renderer {
-    pub fn new() -> Self {
+    pub fn new(board: &BoardDimensions) -> Self {
         let font = FontCache::new();
-        let screen = ScreenConfig::new();
+        let screen = ScreenConfig::new(board);
 
         let mut renderer = Self {
             game_field: render_target(0, 0),
@@ -39,16 +47,21 @@ impl Renderer {
             last_fps_update: 0.0,
             current_fps: 0,
             board_dirty: false,
+            clearing: false,
             flashing: false,
         };
         renderer.set_render_targets();
         renderer
     }
 
-    pub fn draw(&mut self, state: &GameState) {
+    pub fn draw(&mut self, game: &Game, settings: &Settings, show_settings: bool, alpha: f32) {
+        let state = &game.state;
         let current_size = (screen_width(), screen_height());
-        if self.screen.size != current_size {
-            self.screen = ScreenConfig::new();
+        let board = &game.rules().board;
+        if self.screen.size != current_size
+            || (self.screen.columns, self.screen.rows) != (board.width, board.height)
+        {
+            self.screen = ScreenConfig::new(board);
             self.font.update();
             self.text.update(self.font.stats_size as u16);
             self.set_render_targets();
@@ -60,6 +73,13 @@ impl Renderer {
             (get_time() * TIMING.flashing_intervall) as i32 % 2 == 0
         };
 
+        // The board collapses once the line clear animation is over
+        let clearing = !state.board.flashing_lines.is_empty();
+        if self.clearing != clearing {
+            self.board_dirty = true;
+            self.clearing = clearing;
+        }
+
         if self.flashing != new_flashing {
             self.board_dirty = true;
             self.flashing = new_flashing;
@@ -96,19 +116,35 @@ impl Renderer {
                     self.update_placed_pieces(&dummy_board.cells, &[], false);
                 }
 
-                self.draw_start_screen();
+                if show_settings {
+                    self.draw_settings_screen(settings);
+                } else {
+                    self.draw_start_screen(game);
+                }
             }
-            GameStatus::Playing => {
-                self.draw_current_piece(&state.piece);
+            GameStatus::Playing | GameStatus::Paused => {
+                // The locked piece is part of the board until the next one enters
+                if state.board.flashing_lines.is_empty() {
+                    if settings.ghost_piece {
+                        self.draw_ghost_piece(&state.piece, state.drop_distance());
+                    }
+                    self.draw_current_piece(&state.piece, alpha);
+                }
+                if game.rules().hold {
+                    self.draw_hold(&state.hold);
+                }
+                self.draw_queue(&state.queue);
+                if let Some(clear) = state.score.last_clear {
+                    self.draw_clear_callout(clear);
+                }
                 self.draw_stats(state.score.current, state.level.current);
+                self.draw_mode_stats(game);
+                self.draw_streaks(&state.combo);
+                if state.status == GameStatus::Paused {
+                    self.draw_pause_screen(game);
+                }
             }
-            GameStatus::GameOver => {
-                self.draw_game_over(
-                    state.score.current,
-                    state.score.highest,
-                    state.level.current,
-                );
-            }
+            GameStatus::GameOver | GameStatus::Finished => self.draw_results(game),
         }
         self.draw_debug_info();
     }
@@ -126,7 +162,7 @@ impl Renderer {
         set_default_camera();
     }
 
-    fn update_placed_pieces(&mut self, cells: &Board, flashing_lines: &[u8], flashing: bool) {
+    fn update_placed_pieces(&mut self, cells: &Board, flashing_lines: &[i32], flashing: bool) {
         set_camera(&Camera2D {
             zoom: vec2(
                 2.0 / self.screen.field_width,
@@ -165,8 +201,16 @@ impl Renderer {
         self.board_dirty = true;
     }
 
-    pub fn mark_board_dirty(&mut self) {
-        self.board_dirty = true;
+    pub fn handle_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::PieceSpawned(_)
+            | GameEvent::PieceLocked
+            | GameEvent::LinesCleared { .. }
+            | GameEvent::BoardCleared
+            | GameEvent::GarbageAdded
+            | GameEvent::GameOver => self.board_dirty = true,
+            _ => {}
+        }
     }
 
     fn draw_block(&self, x: f32, y: f32, color: Color, offset: bool) {
@@ -177,14 +221,15 @@ impl Renderer {
         };
 
         let size = self.screen.block_size;
-        let pos_x = offset_x + x * size;
-        let pos_y = offset_y + y * size;
+        self.draw_block_at(offset_x + x * size, offset_y + y * size, size, color);
+    }
 
+    fn draw_block_at(&self, pos_x: f32, pos_y: f32, size: f32, color: Color) {
         // Draw block face
         draw_rectangle(pos_x, pos_y, size, size, color);
 
         // Draw inner shading for 3D effect
-        let darker = Color::new(color.r * 0.8, color.g * 0.8, color.b * 0.8, 1.0);
+        let darker = Color::new(color.r * 0.8, color.g * 0.8, color.b * 0.8, color.a);
         draw_rectangle(
             pos_x + size * 0.1,
             pos_y + size * 0.1,
@@ -204,77 +249,225 @@ impl Renderer {
         );
     }
 
-    fn get_button_bounds(&self, button_text: &str, font_size: f32) -> ButtonBounds {
-        let button_dims = measure_text(button_text, None, font_size as u16, 1.0);
-
-        let width = button_dims.width + screen_width() * 0.01;
-        let height = button_dims.height + screen_height() * 0.02;
+    // Buttons share one width and are stacked around the screen center
+    fn get_button_bounds(&self, buttons: &[&str], index: usize) -> ButtonBounds {
+        let font_size = self.font.button_size as u16;
+        let (text_width, text_height) = buttons
+            .iter()
+            .map(|text| measure_text(text, None, font_size, 1.0))
+            .fold((0.0f32, 0.0f32), |(width, height), dims| {
+                (width.max(dims.width), height.max(dims.height))
+            });
+
+        let width = text_width + screen_width() * 0.01;
+        let height = text_height + screen_height() * 0.02;
+        let step = height + screen_height() * 0.015;
+        let top = screen_height() / 2.0 + screen_height() * 0.02
+            - (buttons.len() - 1) as f32 * step / 2.0;
 
         ButtonBounds {
             x: screen_width() / 2.0 - width / 2.0,
-            y: screen_height() / 2.0 + screen_height() * 0.02,
+            y: top + index as f32 * step,
             width,
             height,
         }
     }
 
-    fn draw_start_screen(&mut self) {
+    fn overlay_buttons(status: GameStatus, game: &Game) -> Vec<String> {
+        match status {
+            GameStatus::Start => {
+                let mut labels = vec![
+                    TEXT.start_button.to_string(),
+                    [TEXT.mode, &Self::mode_name(game.mode())].join(""),
+                ];
+                if game.mode().has_start_level() {
+                    labels.push([TEXT.start_level, &(game.start_level() + 1).to_string()].join(""));
+                }
+                labels.push([TEXT.ruleset, &game.rules().name].join(""));
+                labels.push(TEXT.settings_button.to_string());
+                labels
+            }
+            GameStatus::Paused => vec![
+                TEXT.resume_button.to_string(),
+                TEXT.restart_button.to_string(),
+                TEXT.quit_button.to_string(),
+            ],
+            GameStatus::GameOver | GameStatus::Finished => vec![
+                TEXT.gameover_button.to_string(),
+                TEXT.quit_button.to_string(),
+            ],
+            GameStatus::Playing => Vec::new(),
+        }
+    }
+
+    fn mode_name(mode: GameMode) -> String {
+        match mode {
+            GameMode::Marathon { goal: Some(lines) } => format!("{} {}", TEXT.marathon, lines),
+            GameMode::Marathon { goal: None } => format!("{} {}", TEXT.marathon, TEXT.endless),
+            GameMode::Sprint { lines } => format!("{} {}", TEXT.sprint, lines),
+            GameMode::Ultra { seconds } => {
+                format!("{} {}:{:02}", TEXT.ultra, seconds / 60, seconds % 60)
+            }
+            GameMode::Zen { gravity: true } => TEXT.zen.to_string(),
+            GameMode::Zen { gravity: false } => format!("{} {}", TEXT.zen, TEXT.no_gravity),
+            GameMode::Dig {
+                rows,
+                rising: false,
+            } => format!("{} {}", TEXT.dig, rows),
+            GameMode::Dig { rows, rising: true } => {
+                format!("{} {} {}", TEXT.dig, rows, TEXT.rising)
+            }
+        }
+    }
+
+    fn settings_buttons(settings: &Settings) -> Vec<String> {
+        SettingsItem::ALL
+            .iter()
+            .map(|item| match item {
+                SettingsItem::Ghost => {
+                    let value = if settings.ghost_piece {
+                        TEXT.on
+                    } else {
+                        TEXT.off
+                    };
+                    [TEXT.ghost, value].join("")
+                }
+                SettingsItem::Das => format!("{}{} ms", TEXT.das, settings.das_ms),
+                SettingsItem::Arr => format!("{}{} ms", TEXT.arr, settings.arr_ms),
+                SettingsItem::SoftDrop => {
+                    format!("{}{}x", TEXT.soft_drop, settings.soft_drop_factor)
+                }
+                SettingsItem::Back => TEXT.back_button.to_string(),
+            })
+            .collect()
+    }
+
+    fn draw_start_screen(&mut self, game: &Game) {
         let instructions = [
             "Links/Rechts: Bewegen",
-            "Tippen: Drehen",
+            "Tippen links/rechts: Drehen",
             "Halten: Fallen lassen",
         ];
 
-        self.draw_overlay_screen(TEXT.start, TEXT.start_button, instructions);
+        let labels = Self::overlay_buttons(GameStatus::Start, game);
+        let buttons: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.draw_overlay_screen(TEXT.start, &buttons, &instructions);
     }
 
-    fn draw_game_over(&mut self, score: u32, high_score: u32, level: usize) {
-        let score_text = [TEXT.score, &score.to_string()].join("");
-        let highscore_text = [TEXT.highscore, &high_score.to_string()].join("");
-        let level_text = [TEXT.level, &(level + 1).to_string()].join("");
-        let scores = [
-            score_text.as_str(),
-            level_text.as_str(),
-            highscore_text.as_str(),
-        ];
+    fn draw_settings_screen(&mut self, settings: &Settings) {
+        let labels = Self::settings_buttons(settings);
+        let buttons: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.draw_overlay_screen(TEXT.settings, &buttons, &[]);
+    }
 
-        self.draw_overlay_screen(TEXT.gameover, TEXT.gameover_button, scores);
+    fn draw_pause_screen(&mut self, game: &Game) {
+        let state = &game.state;
+        let score_text = [TEXT.score, &state.score.current.to_string()].join("");
+        let level_text = [TEXT.level, &(state.level.current + 1).to_string()].join("");
+        let labels = Self::overlay_buttons(GameStatus::Paused, game);
+        let buttons: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.draw_overlay_screen(
+            TEXT.paused,
+            &buttons,
+            &[score_text.as_str(), level_text.as_str()],
+        );
     }
 
-    fn draw_overlay_screen(&mut self, title: &str, button_text: &str, subtext: [&str; 3]) {
+    // Game over or goal reached, with the results that matter for the mode
+    fn draw_results(&mut self, game: &Game) {
+        let state = &game.state;
+        let title = match (state.status, game.mode()) {
+            (GameStatus::Finished, GameMode::Ultra { .. }) => TEXT.time_up,
+            (GameStatus::Finished, _) => TEXT.finished,
+            _ => TEXT.gameover,
+        };
+
+        let mut lines = Vec::new();
+        match game.mode() {
+            GameMode::Marathon { .. } | GameMode::Ultra { .. } | GameMode::Zen { .. } => {
+                lines.push([TEXT.score, &state.score.current.to_string()].join(""));
+                lines.push([TEXT.level, &(state.level.current + 1).to_string()].join(""));
+                lines.push([TEXT.lines, &state.level.total_lines_cleared.to_string()].join(""));
+                lines.push([TEXT.highscore, &state.score.highest.to_string()].join(""));
+                if let GameMode::Marathon { goal: Some(_) } = game.mode() {
+                    lines.push([TEXT.time, &format_time(state.clock.elapsed)].join(""));
+                }
+            }
+            GameMode::Sprint { lines: goal } => {
+                let clock = &state.clock;
+                lines.push([TEXT.time, &format_time(clock.elapsed)].join(""));
+                lines.push(format!(
+                    "{}{}/{}",
+                    TEXT.lines, state.level.total_lines_cleared, goal
+                ));
+                // Three split times per row
+                for row in clock.splits.chunks(3) {
+                    let splits: Vec<String> = row.iter().map(|&split| format_time(split)).collect();
+                    lines.push(splits.join("  "));
+                }
+            }
+            GameMode::Dig { .. } => {
+                lines.push([TEXT.time, &format_time(state.clock.elapsed)].join(""));
+                lines.push([TEXT.pieces, &state.pieces_placed.to_string()].join(""));
+                if state.status == GameStatus::GameOver {
+                    lines.push([TEXT.garbage, &game.garbage_left().to_string()].join(""));
+                }
+            }
+        }
+
+        // Modes that keep a record race for a best time
+        if game.mode().has_best_time() {
+            let clock = &state.clock;
+            if clock.new_best && state.status == GameStatus::Finished {
+                lines.push(TEXT.new_best_time.to_string());
+            } else if let Some(best) = clock.best {
+                lines.push([TEXT.best_time, &format_time(best)].join(""));
+            }
+        }
+
+        let labels = Self::overlay_buttons(state.status, game);
+        let buttons: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let subtext: Vec<&str> = lines.iter().map(String::as_str).collect();
+        self.draw_overlay_screen(title, &buttons, &subtext);
+    }
+
+    fn draw_overlay_screen(&mut self, title: &str, buttons: &[&str], subtext: &[&str]) {
         let screen_w = screen_width();
         let screen_h = screen_height();
         let center_x = screen_w / 2.0;
-        let center_y = screen_h / 2.0;
         let spacing = screen_h * 0.05;
 
         // Background
         draw_rectangle(0.0, 0.0, screen_w, screen_h, Color::new(0.0, 0.0, 0.0, 0.7));
 
         // Title
+        let first_button = self.get_button_bounds(buttons, 0);
         let title_dims = measure_text(title, None, self.font.size as u16, 1.0);
         draw_text(
             title,
             center_x - title_dims.width / 2.0,
-            center_y - spacing,
+            first_button.y - screen_h * 0.02 - spacing,
             self.font.size,
             WHITE,
         );
 
-        // Button
-        let button = self.get_button_bounds(button_text, self.font.button_size);
-        draw_rectangle(button.x, button.y, button.width, button.height, DARKGRAY);
-        let button_dims = measure_text(button_text, None, self.font.button_size as u16, 1.0);
-        draw_text(
-            button_text,
-            button.x + (button.width - button_dims.width) / 2.0,
-            button.y + (button.height + button_dims.height) / 2.0,
-            self.font.button_size,
-            WHITE,
-        );
+        // Buttons
+        for (i, button_text) in buttons.iter().enumerate() {
+            let button = self.get_button_bounds(buttons, i);
+            draw_rectangle(button.x, button.y, button.width, button.height, DARKGRAY);
+            let button_dims = measure_text(button_text, None, self.font.button_size as u16, 1.0);
+            draw_text(
+                button_text,
+                button.x + (button.width - button_dims.width) / 2.0,
+                button.y + (button.height + button_dims.height) / 2.0,
+                self.font.button_size,
+                WHITE,
+            );
+        }
 
         // Subtext
-        let mut y = center_y + spacing * 3.0;
+        let last_button = self.get_button_bounds(buttons, buttons.len() - 1);
+        let mut y = last_button.y - screen_h * 0.02 + spacing * 3.0;
         for text in subtext {
             let dims = measure_text(text, None, self.font.stats_size as u16, 1.0);
             draw_text(
@@ -288,24 +481,33 @@ impl Renderer {
         }
     }
 
-    pub fn check_click(&self, status: GameStatus) -> bool {
+    // Index of the overlay button clicked this frame, if any
+    fn clicked_button(&self, buttons: &[&str]) -> Option<usize> {
         if !is_mouse_button_pressed(MouseButton::Left) {
-            return false;
+            return None;
         }
 
-        let button_size = self.font.button_size;
-        let button_text = match status {
-            GameStatus::Start => TEXT.start_button,
-            GameStatus::GameOver => TEXT.gameover_button,
-            _ => return false,
-        };
         let (mouse_x, mouse_y) = mouse_position();
-        let button = self.get_button_bounds(button_text, button_size);
+        (0..buttons.len()).find(|&i| {
+            let button = self.get_button_bounds(buttons, i);
+            mouse_x >= button.x
+                && mouse_x <= button.x + button.width
+                && mouse_y >= button.y
+                && mouse_y <= button.y + button.height
+        })
+    }
+
+    pub fn check_click(&self, status: GameStatus, game: &Game) -> Option<usize> {
+        let labels = Self::overlay_buttons(status, game);
+        let buttons: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.clicked_button(&buttons)
+    }
 
-        mouse_x >= button.x
-            && mouse_x <= button.x + button.width
-            && mouse_y >= button.y
-            && mouse_y <= button.y + button.height
+    pub fn check_settings_click(&self, settings: &Settings) -> Option<SettingsItem> {
+        let labels = Self::settings_buttons(settings);
+        let buttons: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.clicked_button(&buttons)
+            .map(|index| SettingsItem::ALL[index])
     }
 
     fn draw_stats(&self, current_score: u32, level: usize) {
@@ -328,7 +530,8 @@ impl Renderer {
         // Level drawing
         let level_num_width = self.text.get_number_width((level + 1) as u32);
         let total_level_width = self.text.level_label_dims.width + level_num_width;
-        let game_field_right = self.screen.offset_x + (BOARD.width as f32 * self.screen.block_size);
+        let game_field_right =
+            self.screen.offset_x + (self.screen.columns as f32 * self.screen.block_size);
         let x_level = if screen_width() > game_field_right + total_level_width + padding * 3.0 {
             game_field_right + padding * 2.0
         } else {
@@ -340,7 +543,7 @@ impl Renderer {
         // Draw texts
         draw_text(TEXT.score, x_score, y, font_size, WHITE);
         draw_text(
-            &current_score.to_string(),
+            current_score.to_string(),
             x_score + self.text.score_label_dims.width,
             y,
             font_size,
@@ -348,7 +551,7 @@ impl Renderer {
         );
         draw_text(TEXT.level, x_level, y, font_size, WHITE);
         draw_text(
-            &(level + 1).to_string(),
+            (level + 1).to_string(),
             x_level + self.text.level_label_dims.width,
             y,
             font_size,
@@ -356,6 +559,56 @@ impl Renderer {
         );
     }
 
+    // Mode specific stats below the level
+    fn draw_mode_stats(&self, game: &Game) {
+        let state = &game.state;
+        let lines = match game.mode() {
+            GameMode::Marathon { goal: None } => return,
+            GameMode::Marathon { goal: Some(lines) } | GameMode::Sprint { lines } => [
+                format_time(state.clock.elapsed),
+                format!(
+                    "{}{}/{}",
+                    TEXT.lines, state.level.total_lines_cleared, lines
+                ),
+            ],
+            // Counts down to zero
+            GameMode::Ultra { seconds } => [
+                [
+                    TEXT.remaining,
+                    &format_time((seconds as f64 - state.clock.elapsed).max(0.0)),
+                ]
+                .join(""),
+                [TEXT.lines, &state.level.total_lines_cleared.to_string()].join(""),
+            ],
+            GameMode::Dig { .. } => [
+                format_time(state.clock.elapsed),
+                [TEXT.garbage, &game.garbage_left().to_string()].join(""),
+            ],
+            GameMode::Zen { .. } => [
+                format_time(state.clock.elapsed),
+                [TEXT.lines, &state.level.total_lines_cleared.to_string()].join(""),
+            ],
+        };
+
+        let font_size = self.font.stats_size;
+        let padding = 10.0;
+        let line_height = self.text.level_label_dims.height + padding;
+        let game_field_right =
+            self.screen.offset_x + (self.screen.columns as f32 * self.screen.block_size);
+
+        let mut y = self.text.level_label_dims.height + padding + line_height;
+        for text in &lines {
+            let width = measure_text(text, None, font_size as u16, 1.0).width;
+            let x = if screen_width() > game_field_right + width + padding * 3.0 {
+                game_field_right + padding * 2.0
+            } else {
+                screen_width() - width - padding
+            };
+            draw_text(text, x, y, font_size, WHITE);
+            y += line_height;
+        }
+    }
+
     fn draw_game_field(&self, screen: &ScreenConfig) {
         // Draw border
         draw_rectangle_lines(
@@ -368,7 +621,7 @@ impl Renderer {
         );
 
         // Vertical lines
-        for x in 1..BOARD.width {
+        for x in 1..screen.columns {
             let thickness = if x % 2 == 0 { 1.5 } else { 1.0 };
             draw_line(
                 x as f32 * screen.block_size,
@@ -381,7 +634,7 @@ impl Renderer {
         }
 
         // Horizontal lines
-        for y in 1..BOARD.height {
+        for y in 1..screen.rows {
             let thickness = if y % 2 == 0 { 2.0 } else { 1.0 };
             draw_line(
                 0.0,
@@ -394,15 +647,15 @@ impl Renderer {
         }
     }
 
-    fn draw_placed_pieces(&mut self, cells: &Board, flashing_lines: &[u8], flashing: bool) {
-        for y in 0..BOARD.height as u8 {
+    fn draw_placed_pieces(&mut self, cells: &Board, flashing_lines: &[i32], flashing: bool) {
+        for y in 0..cells.height {
             let is_line_flashing = flashing_lines.contains(&y);
-            for x in 0..BOARD.width as u8 {
-                if let Some(color) = cells[y as usize][x as usize] {
+            for x in 0..cells.width {
+                if let Some(cell) = cells.get(x, y) {
                     let draw_color = if flashing && is_line_flashing {
                         WHITE
                     } else {
-                        color
+                        cell.color()
                     };
 
                     self.draw_block(x as f32, y as f32, draw_color, false);
@@ -411,16 +664,161 @@ impl Renderer {
         }
     }
 
-    fn draw_current_piece(&self, piece: &PieceState) {
+    // Draws the piece `alpha` of the way from its previous tick position. Jumps
+    // of more than one cell, like instant shifts, are not smoothed.
+    fn draw_current_piece(&self, piece: &PieceState, alpha: f32) {
+        let (x, y) = piece.position;
+        let (prev_x, prev_y) = piece.previous_position;
+        let (offset_x, offset_y) = if (x - prev_x).abs() <= 1 && (y - prev_y).abs() <= 1 {
+            (
+                (prev_x - x) as f32 * (1.0 - alpha),
+                (prev_y - y) as f32 * (1.0 - alpha),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
+        for &(dx, dy) in &piece.rotated {
+            let draw_y = (y + dy) as f32 + offset_y;
+            if draw_y >= 0.0 {
+                self.draw_block((x + dx) as f32 + offset_x, draw_y, piece.typ.color(), true);
+            }
+        }
+    }
+
+    // Translucent copy of the piece at its hard drop landing row
+    fn draw_ghost_piece(&self, piece: &PieceState, drop_distance: i32) {
+        let color = piece.typ.color();
+        let ghost_color = Color::new(color.r, color.g, color.b, 0.3);
         for &(x, y) in &piece.rotated {
             let draw_x = piece.position.0 + x;
-            let draw_y = piece.position.1 + y;
+            let draw_y = piece.position.1 + y + drop_distance;
             if draw_y >= 0 {
-                self.draw_block(draw_x as f32, draw_y as f32, piece.typ.color(), true);
+                self.draw_block(draw_x as f32, draw_y as f32, ghost_color, true);
             }
         }
     }
 
+    // Draws a piece centered in a 4x2 block slot starting at `(x, y)`
+    fn draw_preview_piece(&self, piece: Tetromino, x: f32, y: f32, color: Color) {
+        let size = self.screen.preview_block_size;
+        let shape = piece.shape();
+        let min_x = shape.iter().map(|&(x, _)| x).min().unwrap();
+        let max_x = shape.iter().map(|&(x, _)| x).max().unwrap();
+        let min_y = shape.iter().map(|&(_, y)| y).min().unwrap();
+        let max_y = shape.iter().map(|&(_, y)| y).max().unwrap();
+        let start_x = x + (4 - (max_x - min_x + 1)) as f32 * size / 2.0;
+        let start_y = y + (2 - (max_y - min_y + 1)) as f32 * size / 2.0;
+
+        for &(dx, dy) in &shape {
+            self.draw_block_at(
+                start_x + (dx - min_x) as f32 * size,
+                start_y + (dy - min_y) as f32 * size,
+                size,
+                color,
+            );
+        }
+    }
+
+    fn draw_hold(&self, hold: &HoldState) {
+        let font_size = self.font.stats_size;
+        let label_dims = measure_text(TEXT.hold, None, font_size as u16, 1.0);
+        draw_text(
+            TEXT.hold,
+            self.screen.hold_x,
+            self.screen.hold_y - label_dims.height,
+            font_size,
+            WHITE,
+        );
+
+        if let Some(piece) = hold.piece {
+            // Grey out the piece until it can be swapped again
+            let color = if hold.used { GRAY } else { piece.color() };
+            self.draw_preview_piece(piece, self.screen.hold_x, self.screen.hold_y, color);
+        }
+    }
+
+    fn draw_queue(&self, queue: &VecDeque<Tetromino>) {
+        let font_size = self.font.stats_size;
+        let label_dims = measure_text(TEXT.next, None, font_size as u16, 1.0);
+        draw_text(
+            TEXT.next,
+            self.screen.queue_x,
+            self.screen.queue_y - label_dims.height,
+            font_size,
+            WHITE,
+        );
+
+        // Each piece gets two rows plus a one row gap
+        let slot_height = self.screen.preview_block_size * 3.0;
+        for (i, &piece) in queue.iter().enumerate() {
+            let y = self.screen.queue_y + i as f32 * slot_height;
+            self.draw_preview_piece(piece, self.screen.queue_x, y, piece.color());
+        }
+    }
+
+    // Names special clears, e.g. "T-SPIN DOUBLE" or "TETRIS"
+    fn draw_clear_callout(&self, clear: LineClear) {
+        let lines = TEXT.clear_names[clear.lines.min(4) as usize];
+        let mut text = match clear.kind {
+            ClearKind::TSpin => [TEXT.tspin, lines].join(" ").trim_end().to_string(),
+            ClearKind::TSpinMini => [TEXT.tspin_mini, lines].join(" ").trim_end().to_string(),
+            ClearKind::Normal if clear.lines == 4 => lines.to_string(),
+            ClearKind::Normal => String::new(),
+        };
+        if clear.back_to_back {
+            text = [TEXT.back_to_back, &text].join(" ");
+        }
+
+        let mut callouts = Vec::new();
+        if !text.is_empty() {
+            callouts.push(text.as_str());
+        }
+        if clear.perfect_clear {
+            callouts.push(TEXT.perfect_clear);
+        }
+
+        let font_size = self.font.stats_size;
+        let x_center = self.screen.offset_x + self.screen.field_width / 2.0;
+        let mut y = self.screen.offset_y + self.screen.field_height * 0.3;
+        for line in callouts {
+            let dims = measure_text(line, None, font_size as u16, 1.0);
+            draw_text(line, x_center - dims.width / 2.0, y, font_size, WHITE);
+            y += dims.height * 1.5;
+        }
+    }
+
+    // Combo and back-to-back counters below the score, while a streak runs
+    fn draw_streaks(&self, combo: &ComboState) {
+        let font_size = self.font.stats_size;
+        let padding = 10.0;
+        let line_height = self.text.score_label_dims.height + padding;
+
+        let counters = [
+            (TEXT.combo, combo.combo.filter(|&combo| combo > 0)),
+            (
+                TEXT.back_to_back_counter,
+                combo.back_to_back.checked_sub(1).filter(|&chain| chain > 0),
+            ),
+        ];
+
+        let mut y = self.text.score_label_dims.height + padding + line_height;
+        for (label, value) in counters {
+            let Some(value) = value else {
+                continue;
+            };
+            let text = [label, &value.to_string()].join("");
+            let width = measure_text(&text, None, font_size as u16, 1.0).width;
+            let x = if self.screen.offset_x > width + padding * 3.0 {
+                self.screen.offset_x - width - padding * 2.0
+            } else {
+                padding
+            };
+            draw_text(&text, x, y, font_size, WHITE);
+            y += line_height;
+        }
+    }
+
     fn draw_debug_info(&mut self) {
         if !cfg!(debug_assertions) {
             return;
@@ -445,3 +843,14 @@ impl Renderer {
         draw_text(&fps_text, x, y, font_size, WHITE);
     }
 }
+
+// Minutes, seconds and milliseconds, like 1:02.345
+fn format_time(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
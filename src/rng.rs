@@ -0,0 +1,24 @@
+// Seedable SplitMix64 generator, so a game can be replayed from its seed
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Returns a value in `low..high`
+    pub fn gen_range(&mut self, low: i32, high: i32) -> i32 {
+        let span = (high - low).max(1) as u64;
+        low + (self.next_u64() % span) as i32
+    }
+}
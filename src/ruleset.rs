@@ -0,0 +1,145 @@
+use serde::Deserialize;
+
+use crate::{
+    config::{
+        BoardDimensions, LevelConfig, LockConfig, ScoreConfig, TopOutConfig, BOARD, GRAVITY,
+        LEVEL_CONFIGS, QUEUE,
+    },
+    randomizer::RandomizerKind,
+    tetromino::RotationSystem,
+};
+
+// Built-in rulesets, selectable by name. Guideline is the compiled-in default,
+// the others are embedded so they also work on wasm.
+pub const PRESETS: [(&str, Option<&str>); 3] = [
+    ("guideline", None),
+    ("nes", Some(include_str!("../rulesets/nes.toml"))),
+    ("tgm", Some(include_str!("../rulesets/tgm.toml"))),
+];
+
+// Everything that defines how a game plays. Fields left out of a ruleset
+// file fall back to the guideline values, except the name.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ruleset {
+    // Required in files, records and high scores are stored under it
+    #[serde(default)]
+    pub name: String,
+    pub board: BoardDimensions,
+    // Gravity and lock delay per level
+    pub levels: Vec<LevelConfig>,
+    pub score: ScoreConfig,
+    pub lock: LockConfig,
+    pub top_out: TopOutConfig,
+    pub randomizer: RandomizerKind,
+    pub rotation: RotationSystem,
+    pub preview_count: usize,
+    pub hold: bool,
+    pub hard_drop: bool,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            name: "Guideline".to_string(),
+            board: BOARD,
+            levels: LEVEL_CONFIGS.to_vec(),
+            score: ScoreConfig::default(),
+            lock: LockConfig::default(),
+            top_out: TopOutConfig::default(),
+            randomizer: RandomizerKind::default(),
+            rotation: RotationSystem::default(),
+            preview_count: QUEUE.preview_count,
+            hold: true,
+            hard_drop: true,
+        }
+    }
+}
+
+impl Ruleset {
+    pub fn presets() -> Vec<Ruleset> {
+        PRESETS
+            .iter()
+            .map(|(_, source)| match source {
+                Some(source) => Self::from_toml(source).expect("invalid built-in ruleset"),
+                None => Self::default(),
+            })
+            .collect()
+    }
+
+    // Name as used in storage keys, like `nes_classic`
+    pub fn key(&self) -> String {
+        self.name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    // Settings for a zero-based level, levels past the table keep the last entry
+    pub fn level(&self, level: usize) -> &LevelConfig {
+        &self.levels[level.min(self.levels.len() - 1)]
+    }
+
+    // Seconds per row at a zero-based level, 0 drops pieces instantly
+    pub fn fall_interval(&self, level: usize) -> f32 {
+        self.level(level)
+            .fall_interval
+            .unwrap_or_else(|| guideline_gravity(level + 1))
+    }
+
+    // Total lines cleared needed to leave a zero-based level
+    pub fn lines_required(&self, level: usize) -> u32 {
+        let extra_levels = level.saturating_sub(self.levels.len() - 1) as u32;
+        self.level(level).lines_required + extra_levels * GRAVITY.lines_per_level
+    }
+
+    pub fn from_toml(source: &str) -> Result<Ruleset, String> {
+        let ruleset: Ruleset = toml::from_str(source).map_err(|e| e.to_string())?;
+        ruleset.validate()
+    }
+
+    pub fn from_json(source: &str) -> Result<Ruleset, String> {
+        let ruleset: Ruleset = serde_json::from_str(source).map_err(|e| e.to_string())?;
+        ruleset.validate()
+    }
+
+    // Reads a `.json` or `.toml` ruleset file
+    pub fn from_file(path: &str) -> Result<Ruleset, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        if path.ends_with(".json") {
+            Self::from_json(&source)
+        } else {
+            Self::from_toml(&source)
+        }
+        .map_err(|e| format!("{path}: {e}"))
+    }
+
+    fn validate(self) -> Result<Ruleset, String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        // Every piece has to fit in the field
+        if self.board.width < 4 || self.board.height < 4 || self.board.hidden_rows < 0 {
+            return Err("board must be at least 4x4".to_string());
+        }
+        if self.levels.is_empty() {
+            return Err("at least one level is required".to_string());
+        }
+        Ok(self)
+    }
+}
+
+// Guideline curve, (0.8 - (level - 1) * 0.007)^(level - 1) seconds per row
+fn guideline_gravity(level: usize) -> f32 {
+    if level >= GRAVITY.instant_level {
+        return 0.0;
+    }
+    let steps = level.saturating_sub(1) as f32;
+    (0.8 - steps * 0.007).powf(steps)
+}
@@ -1,39 +1,82 @@
 use macroquad::prelude::*;
 
-use crate::config::BOARD;
+use crate::config::BoardDimensions;
 
 pub struct ScreenConfig {
+    pub columns: i32,
+    pub rows: i32,
     pub block_size: f32,
     pub offset_x: f32,
     pub offset_y: f32,
     pub field_width: f32,
     pub field_height: f32,
+    pub preview_block_size: f32,
+    pub hold_x: f32,
+    pub hold_y: f32,
+    pub queue_x: f32,
+    pub queue_y: f32,
     pub size: (f32, f32),
 }
 
 impl ScreenConfig {
-    pub fn new() -> Self {
+    pub fn new(board: &BoardDimensions) -> Self {
         let screen_width = screen_width();
         let screen_height = screen_height();
+        let portrait = screen_height > screen_width;
+
+        // Side panels are 4 preview blocks wide plus half a block margin on each side.
+        // Landscape puts hold left and queue right, portrait stacks both on the right.
+        let preview_scale = if portrait { 0.5 } else { 0.6 };
+        let panel_width = preview_scale * 5.0;
+        let panel_count = if portrait { 1.0 } else { 2.0 };
 
         // Calculate optimal block size
-        let scale_x = screen_width / BOARD.width as f32;
-        let scale_y = screen_height / BOARD.height as f32;
+        let scale_x = screen_width / (board.width as f32 + panel_width * panel_count);
+        let scale_y = screen_height / board.height as f32;
         let block_size: f32 = scale_x.min(scale_y) * 0.95; // 95% of available space
 
-        // Center the game field
-        let offset_x = (screen_width - (BOARD.width as f32 * block_size)) / 2.0;
-        let offset_y = (screen_height - (BOARD.height as f32 * block_size)) / 2.0;
+        let field_width = board.width as f32 * block_size;
+        let field_height = board.height as f32 * block_size;
+        let preview_block_size = block_size * preview_scale;
+        let panel_margin = preview_block_size * 0.5;
+
+        // Center the game field, together with its panel in portrait
+        let offset_x = if portrait {
+            (screen_width - field_width - panel_width * block_size) / 2.0
+        } else {
+            (screen_width - field_width) / 2.0
+        };
+        let offset_y = (screen_height - field_height) / 2.0;
 
-        let field_width = BOARD.width as f32 * block_size;
-        let field_height = BOARD.height as f32 * block_size;
+        let right_panel_x = offset_x + field_width + panel_margin;
+        let hold_y = offset_y + block_size * 3.0;
+        let (hold_x, queue_x, queue_y) = if portrait {
+            (
+                right_panel_x,
+                right_panel_x,
+                hold_y + preview_block_size * 5.0,
+            )
+        } else {
+            (
+                offset_x - panel_margin - preview_block_size * 4.0,
+                right_panel_x,
+                hold_y,
+            )
+        };
 
         Self {
+            columns: board.width,
+            rows: board.height,
             block_size,
             offset_x,
             offset_y,
             field_width,
             field_height,
+            preview_block_size,
+            hold_x,
+            hold_y,
+            queue_x,
+            queue_y,
             size: (screen_width, screen_height),
         }
     }
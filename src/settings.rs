@@ -0,0 +1,74 @@
+use crate::storage;
+
+const DAS_CHOICES: [u32; 8] = [50, 83, 100, 117, 133, 167, 200, 250];
+const ARR_CHOICES: [u32; 6] = [0, 16, 33, 50, 83, 100];
+const SOFT_DROP_CHOICES: [u32; 5] = [5, 10, 20, 40, 80];
+
+#[derive(Clone, Copy)]
+pub enum SettingsItem {
+    Ghost,
+    Das,
+    Arr,
+    SoftDrop,
+    Back,
+}
+
+impl SettingsItem {
+    pub const ALL: [SettingsItem; 5] = [
+        SettingsItem::Ghost,
+        SettingsItem::Das,
+        SettingsItem::Arr,
+        SettingsItem::SoftDrop,
+        SettingsItem::Back,
+    ];
+}
+
+// Player preferences, persisted between sessions
+pub struct Settings {
+    pub ghost_piece: bool,
+    // Delayed auto shift: how long a direction is held before it repeats
+    pub das_ms: u32,
+    // Auto repeat rate: time between repeated moves, 0 shifts straight to the wall
+    pub arr_ms: u32,
+    // How many times faster than gravity a soft drop falls
+    pub soft_drop_factor: u32,
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        Self {
+            ghost_piece: storage::get_value("ghost_piece", 1) != 0,
+            das_ms: storage::get_value("das", 167),
+            arr_ms: storage::get_value("arr", 33),
+            soft_drop_factor: storage::get_value("soft_drop_factor", 20).max(1),
+        }
+    }
+
+    pub fn save(&self) {
+        storage::set_value("ghost_piece", self.ghost_piece as u32);
+        storage::set_value("das", self.das_ms);
+        storage::set_value("arr", self.arr_ms);
+        storage::set_value("soft_drop_factor", self.soft_drop_factor);
+    }
+
+    // Steps the item to its next value, wrapping around
+    pub fn cycle(&mut self, item: SettingsItem) {
+        match item {
+            SettingsItem::Ghost => self.ghost_piece = !self.ghost_piece,
+            SettingsItem::Das => self.das_ms = next_choice(self.das_ms, &DAS_CHOICES),
+            SettingsItem::Arr => self.arr_ms = next_choice(self.arr_ms, &ARR_CHOICES),
+            SettingsItem::SoftDrop => {
+                self.soft_drop_factor = next_choice(self.soft_drop_factor, &SOFT_DROP_CHOICES)
+            }
+            SettingsItem::Back => {}
+        }
+    }
+}
+
+fn next_choice(current: u32, choices: &[u32]) -> u32 {
+    choices
+        .iter()
+        .copied()
+        .find(|&choice| choice > current)
+        .unwrap_or(choices[0])
+}
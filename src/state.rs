@@ -1,42 +1,224 @@
-use macroquad::color::Color;
+use std::collections::VecDeque;
+
+use macroquad::color::{Color, GRAY};
 use smallvec::SmallVec;
 
 use crate::{
-    config::BOARD,
+    config::BoardDimensions,
     dummy_board::DummyBoard,
-    storage,
     tetromino::{RotationState, Tetromino},
 };
 
-pub type Board = [[Option<Color>; BOARD.width as usize]; BOARD.height as usize];
+// What fills an occupied board cell
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cell {
+    Piece(Tetromino),
+    // Pushed in from below in Dig mode
+    Garbage,
+}
+
+impl Cell {
+    pub fn color(&self) -> Color {
+        match self {
+            Cell::Piece(typ) => typ.color(),
+            Cell::Garbage => GRAY,
+        }
+    }
+}
+
+// Grid of placed blocks. Rows 0 to `height - 1` are the visible field, the
+// `hidden_rows` above them have negative y and are never drawn.
+#[derive(Clone)]
+pub struct Board {
+    pub width: i32,
+    pub height: i32,
+    pub hidden_rows: i32,
+    rows: Vec<Vec<Option<Cell>>>,
+}
+
+impl Board {
+    pub fn new(dimensions: &BoardDimensions) -> Self {
+        Self {
+            width: dimensions.width,
+            height: dimensions.height,
+            hidden_rows: dimensions.hidden_rows,
+            rows: vec![
+                vec![None; dimensions.width as usize];
+                (dimensions.hidden_rows + dimensions.height) as usize
+            ],
+        }
+    }
+
+    // Topmost row that can hold blocks
+    pub fn top(&self) -> i32 {
+        -self.hidden_rows
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (0..self.width).contains(&x) && (self.top()..self.height).contains(&y)
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<Cell> {
+        if self.contains(x, y) {
+            self.rows[(y + self.hidden_rows) as usize][x as usize]
+        } else {
+            None
+        }
+    }
+
+    // Cells outside the board are dropped
+    pub fn set(&mut self, x: i32, y: i32, cell: Option<Cell>) {
+        if self.contains(x, y) {
+            self.rows[(y + self.hidden_rows) as usize][x as usize] = cell;
+        }
+    }
+
+    pub fn row(&self, y: i32) -> &[Option<Cell>] {
+        &self.rows[(y + self.hidden_rows) as usize]
+    }
+
+    pub fn is_row_empty(&self, y: i32) -> bool {
+        self.row(y).iter().all(Option::is_none)
+    }
+
+    pub fn is_row_full(&self, y: i32) -> bool {
+        self.row(y).iter().all(Option::is_some)
+    }
+
+    // Empties every row above `y`, hidden rows included
+    pub fn clear_above(&mut self, y: i32) {
+        let end = (y + self.hidden_rows).clamp(0, self.rows.len() as i32) as usize;
+        for row in &mut self.rows[..end] {
+            row.fill(None);
+        }
+    }
+
+    // Inserts a row at the bottom and pushes everything else up. Returns
+    // false if blocks were pushed off the top.
+    pub fn push_row(&mut self, row: Vec<Option<Cell>>) -> bool {
+        let removed = self.rows.remove(0);
+        self.rows.push(row);
+        removed.iter().all(Option::is_none)
+    }
+
+    // Removes the rows and lets everything above them fall down
+    pub fn remove_rows(&mut self, rows: &[i32]) {
+        let hidden_rows = self.hidden_rows;
+        let mut y = self.top();
+        self.rows.retain(|_| {
+            let keep = !rows.contains(&y);
+            y += 1;
+            keep
+        });
+        let missing = (hidden_rows + self.height) as usize - self.rows.len();
+        for _ in 0..missing {
+            self.rows.insert(0, vec![None; self.width as usize]);
+        }
+    }
+}
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum GameStatus {
     Start,
     Playing,
+    Paused,
     GameOver,
+    // Reached the goal of the game mode
+    Finished,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ClearKind {
+    Normal,
+    TSpinMini,
+    TSpin,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LineClear {
+    pub lines: u32,
+    pub kind: ClearKind,
+    pub back_to_back: bool,
+    pub perfect_clear: bool,
 }
 
 pub struct PieceState {
     pub typ: Tetromino,
     pub position: (i32, i32),
+    // Position at the start of the last tick, for smooth rendering between ticks
+    pub previous_position: (i32, i32),
     pub rotated: [(i32, i32); 4],
     pub rotation: RotationState,
+    // Kick used by the last successful action, if that action was a rotation
+    pub last_kick: Option<usize>,
+    // Whether that rotation turned the piece around, which uses other kicks
+    pub last_flip: bool,
+}
+
+impl PieceState {
+    // Spawns centered with its lowest blocks just above the visible field
+    pub fn spawn(typ: Tetromino, board_width: i32) -> Self {
+        let rotated = typ.cells(RotationState::Zero);
+        let bottom = rotated.iter().map(|&(_, y)| y).max().unwrap();
+        let position = ((board_width - typ.box_size()) / 2, -1 - bottom);
+        Self {
+            typ,
+            position,
+            previous_position: position,
+            rotated,
+            rotation: RotationState::Zero,
+            last_kick: None,
+            last_flip: false,
+        }
+    }
 }
 
 pub struct TimingState {
     pub fall_interval: f32,
     pub fall_timer: f32,
     pub line_clear_timer: f32,
+    pub lock_timer: f32,
+    pub lock_resets: u32,
+    // Lowest row the current piece has reached, for lock reset rules
+    pub lowest_row: i32,
+    pub callout_timer: f32,
+    // Time towards the next rising garbage row
+    pub garbage_timer: f32,
 }
 
 pub struct BoardState {
     pub cells: Board,
-    pub flashing_lines: SmallVec<[u8; 4]>,
+    pub flashing_lines: SmallVec<[i32; 4]>,
+}
+
+impl BoardState {
+    // Walls and floor count as occupied, the space above the board does not
+    pub fn is_occupied(&self, x: i32, y: i32) -> bool {
+        !(0..self.cells.width).contains(&x)
+            || y >= self.cells.height
+            || self.cells.get(x, y).is_some()
+    }
+
+    // Whether the cells fit at `position` without leaving the field or overlapping blocks
+    pub fn fits(&self, cells: &[(i32, i32); 4], position: (i32, i32)) -> bool {
+        cells
+            .iter()
+            .all(|&(x, y)| !self.is_occupied(position.0 + x, position.1 + y))
+    }
 }
 
 pub struct ScoreState {
     pub current: u32,
     pub highest: u32,
+    // Shown as a callout while `callout_timer` runs
+    pub last_clear: Option<LineClear>,
+}
+
+pub struct ComboState {
+    // Consecutive line clearing placements after the first one
+    pub combo: Option<u32>,
+    // Tetrises and T-spin clears in a row, without a plain clear in between
+    pub back_to_back: u32,
 }
 
 pub struct LevelState {
@@ -44,45 +226,100 @@ pub struct LevelState {
     pub total_lines_cleared: u32,
 }
 
+pub struct ClockState {
+    // Seconds played
+    pub elapsed: f64,
+    // Time whenever another `split_lines` lines were cleared
+    pub splits: Vec<f64>,
+    // Personal best time for the game mode
+    pub best: Option<f64>,
+    pub new_best: bool,
+}
+
+pub struct HoldState {
+    pub piece: Option<Tetromino>,
+    // Only one hold is allowed per placed piece
+    pub used: bool,
+}
+
 pub struct GameState {
     pub status: GameStatus,
     pub score: ScoreState,
+    pub combo: ComboState,
     pub dummy_board: Option<DummyBoard>,
     pub board: BoardState,
     pub piece: PieceState,
+    pub queue: VecDeque<Tetromino>,
+    pub hold: HoldState,
     pub timing: TimingState,
     pub level: LevelState,
+    pub clock: ClockState,
+    pub pieces_placed: u32,
 }
 
 impl GameState {
-    pub fn new() -> Self {
-        let initial_piece = Tetromino::random();
+    pub fn new(
+        initial_piece: Tetromino,
+        queue: VecDeque<Tetromino>,
+        dimensions: &BoardDimensions,
+        dummy_board: Option<DummyBoard>,
+    ) -> Self {
         Self {
             status: GameStatus::Start,
             score: ScoreState {
                 current: 0,
-                highest: storage::get_high_score(),
+                highest: 0,
+                last_clear: None,
             },
-            dummy_board: Some(DummyBoard::new()),
+            combo: ComboState {
+                combo: None,
+                back_to_back: 0,
+            },
+            dummy_board,
             board: BoardState {
-                cells: [[None; BOARD.width as usize]; BOARD.height as usize],
+                cells: Board::new(dimensions),
                 flashing_lines: SmallVec::new(),
             },
-            piece: PieceState {
-                typ: initial_piece,
-                rotated: initial_piece.shape(),
-                position: (BOARD.width / 2 - 2, -1),
-                rotation: RotationState::Zero,
+            piece: PieceState::spawn(initial_piece, dimensions.width),
+            queue,
+            hold: HoldState {
+                piece: None,
+                used: false,
             },
             timing: TimingState {
                 fall_timer: 0.0,
                 fall_interval: 0.48,
                 line_clear_timer: 0.0,
+                lock_timer: 0.0,
+                lock_resets: 0,
+                lowest_row: -1,
+                callout_timer: 0.0,
+                garbage_timer: 0.0,
             },
             level: LevelState {
                 current: 0,
                 total_lines_cleared: 0,
             },
+            clock: ClockState {
+                elapsed: 0.0,
+                splits: Vec::new(),
+                best: None,
+                new_best: false,
+            },
+            pieces_placed: 0,
+        }
+    }
+
+    // Rows the current piece can fall before it lands
+    pub fn drop_distance(&self) -> i32 {
+        let piece = &self.piece;
+        let mut distance = 0;
+        while self.board.fits(
+            &piece.rotated,
+            (piece.position.0, piece.position.1 + distance + 1),
+        ) {
+            distance += 1;
         }
+        distance
     }
 }
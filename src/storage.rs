@@ -1,38 +1,46 @@
 #[cfg(target_arch = "wasm32")]
 extern "C" {
-    fn js_get_high_score() -> u32;
-    fn js_save_high_score(score: u32);
+    fn js_get_value(key: *const u8, key_len: usize, default: u32) -> u32;
+    fn js_save_value(key: *const u8, key_len: usize, value: u32);
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn js_get_high_score() -> u32 {
+fn js_get_value(key: &str, default: u32) -> u32 {
     // Mock implementation for local builds
-    println!("Using local mock for js_get_high_score");
-    0
+    println!("Using local mock for js_get_value: {}", key);
+    default
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn js_save_high_score(score: u32) {
+fn js_save_value(key: &str, value: u32) {
     // Mock implementation for local builds
-    println!("Using local mock for js_save_high_score: {}", score);
+    println!("Using local mock for js_save_value: {} = {}", key, value);
 }
 
-pub fn get_high_score() -> u32 {
+pub fn get_value(key: &str, default: u32) -> u32 {
     #[cfg(target_arch = "wasm32")]
     unsafe {
-        js_get_high_score()
+        js_get_value(key.as_ptr(), key.len(), default)
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    js_get_high_score()
+    js_get_value(key, default)
 }
 
-pub fn update_high_score(score: u32) {
+pub fn set_value(key: &str, value: u32) {
     #[cfg(target_arch = "wasm32")]
     unsafe {
-        js_save_high_score(score);
+        js_save_value(key.as_ptr(), key.len(), value);
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    js_save_high_score(score);
+    js_save_value(key, value);
+}
+
+pub fn get_high_score(key: &str) -> u32 {
+    get_value(key, 0)
+}
+
+pub fn update_high_score(key: &str, score: u32) {
+    set_value(key, score);
 }
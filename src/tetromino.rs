@@ -1,7 +1,10 @@
 use macroquad::prelude::*;
-use rand::gen_range;
+use serde::Deserialize;
+use smallvec::SmallVec;
 
-#[derive(Clone, Copy, PartialEq)]
+use crate::rng::Rng;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Tetromino {
     I,
     O,
@@ -13,6 +16,16 @@ pub enum Tetromino {
 }
 
 impl Tetromino {
+    pub const ALL: [Tetromino; 7] = [
+        Tetromino::I,
+        Tetromino::O,
+        Tetromino::T,
+        Tetromino::S,
+        Tetromino::Z,
+        Tetromino::J,
+        Tetromino::L,
+    ];
+
     pub fn shape(&self) -> [(i32, i32); 4] {
         match self {
             Tetromino::I => [(0, 1), (1, 1), (2, 1), (3, 1)],
@@ -25,6 +38,49 @@ impl Tetromino {
         }
     }
 
+    // Side of the square the piece rotates in
+    pub fn box_size(&self) -> i32 {
+        match self {
+            Tetromino::I => 4,
+            Tetromino::O => 2,
+            _ => 3,
+        }
+    }
+
+    pub fn cells(&self, rotation: RotationState) -> [(i32, i32); 4] {
+        let last = self.box_size() - 1;
+        let mut cells = self.shape();
+        for _ in 0..rotation as u8 {
+            cells = cells.map(|(x, y)| (last - y, x));
+        }
+        cells
+    }
+
+    // SRS wall kicks to try, in order, when turning from one state to another.
+    // Each kick is the difference of the two states' offsets; y points down.
+    pub fn kicks(&self, from: RotationState, to: RotationState) -> SmallVec<[(i32, i32); 6]> {
+        if *self == Tetromino::O {
+            return SmallVec::from_slice(&[(0, 0)]);
+        }
+
+        // SRS has no 180 kicks, so use the common SRS+ table
+        if to == from.flip() {
+            let kicks = &FLIP_KICKS[from as usize];
+            return kicks.iter().map(|&(x, y)| (x, -y)).collect();
+        }
+
+        let offsets = match self {
+            Tetromino::I => &I_OFFSETS,
+            _ => &JLSTZ_OFFSETS,
+        };
+        let from = offsets[from as usize];
+        let to = offsets[to as usize];
+        from.iter()
+            .zip(to.iter())
+            .map(|(from, to)| (from.0 - to.0, to.1 - from.1))
+            .collect()
+    }
+
     pub fn color(&self) -> Color {
         match self {
             Tetromino::I => BLUE,
@@ -37,21 +93,35 @@ impl Tetromino {
         }
     }
 
-    pub fn random() -> Self {
-        const PIECES: [Tetromino; 7] = [
-            Tetromino::I,
-            Tetromino::O,
-            Tetromino::T,
-            Tetromino::S,
-            Tetromino::Z,
-            Tetromino::J,
-            Tetromino::L,
-        ];
-        PIECES[gen_range(0, PIECES.len())]
+    pub fn random(rng: &mut Rng) -> Self {
+        Self::ALL[rng.gen_range(0, Self::ALL.len() as i32) as usize]
     }
 }
 
-#[derive(Clone, Copy)]
+// SRS offset data per rotation state, with y pointing up as in the guideline
+const JLSTZ_OFFSETS: [[(i32, i32); 5]; 4] = [
+    [(0, 0), (0, 0), (0, 0), (0, 0), (0, 0)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (0, 0), (0, 0), (0, 0), (0, 0)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+];
+
+const I_OFFSETS: [[(i32, i32); 5]; 4] = [
+    [(0, 0), (-1, 0), (2, 0), (-1, 0), (2, 0)],
+    [(0, 0), (1, 0), (1, 0), (1, 1), (1, -2)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, -1)],
+    [(0, 0), (0, 0), (0, 0), (0, -2), (0, 1)],
+];
+
+// 180 kicks indexed by the starting state
+const FLIP_KICKS: [[(i32, i32); 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum RotationState {
     Zero = 0,
     Right = 1,
@@ -60,7 +130,7 @@ pub enum RotationState {
 }
 
 impl RotationState {
-    pub fn next(&self) -> Self {
+    pub fn cw(&self) -> Self {
         match self {
             RotationState::Zero => RotationState::Right,
             RotationState::Right => RotationState::Two,
@@ -68,4 +138,190 @@ impl RotationState {
             RotationState::Left => RotationState::Zero,
         }
     }
+
+    pub fn ccw(&self) -> Self {
+        match self {
+            RotationState::Zero => RotationState::Left,
+            RotationState::Right => RotationState::Zero,
+            RotationState::Two => RotationState::Right,
+            RotationState::Left => RotationState::Two,
+        }
+    }
+
+    pub fn flip(&self) -> Self {
+        match self {
+            RotationState::Zero => RotationState::Two,
+            RotationState::Right => RotationState::Left,
+            RotationState::Two => RotationState::Zero,
+            RotationState::Left => RotationState::Right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RotationSystem {
+    // Guideline SRS kick tables
+    #[default]
+    Srs,
+    // Only one column to the right or left, like the arcade games
+    Simple,
+    // Rotation fails unless the piece fits in place
+    None,
+}
+
+impl RotationSystem {
+    pub fn kicks(
+        &self,
+        piece: Tetromino,
+        from: RotationState,
+        to: RotationState,
+    ) -> SmallVec<[(i32, i32); 6]> {
+        match self {
+            RotationSystem::Srs => piece.kicks(from, to),
+            RotationSystem::Simple if piece != Tetromino::I => {
+                SmallVec::from_slice(&[(0, 0), (1, 0), (-1, 0)])
+            }
+            _ => SmallVec::from_slice(&[(0, 0)]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RotationState::{Left, Right, Two, Zero};
+
+    // A transition and its kicks as published in the guideline, with y pointing up
+    type Transition = (RotationState, RotationState, [(i32, i32); 5]);
+
+    const JLSTZ_KICKS: [Transition; 8] = [
+        (Zero, Right, [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+        (Right, Zero, [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)]),
+        (Right, Two, [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)]),
+        (Two, Right, [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+        (Two, Left, [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)]),
+        (Left, Two, [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+        (Left, Zero, [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+        (Zero, Left, [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)]),
+    ];
+
+    const I_KICKS: [Transition; 8] = [
+        (Zero, Right, [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]),
+        (Right, Zero, [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]),
+        (Right, Two, [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]),
+        (Two, Right, [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)]),
+        (Two, Left, [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]),
+        (Left, Two, [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]),
+        (Left, Zero, [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)]),
+        (Zero, Left, [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]),
+    ];
+
+    fn assert_kicks(piece: Tetromino, table: &[Transition]) {
+        for (from, to, kicks) in table {
+            let expected: Vec<(i32, i32)> = kicks.iter().map(|&(x, y)| (x, -y)).collect();
+            assert_eq!(
+                piece.kicks(*from, *to).to_vec(),
+                expected,
+                "{piece:?} {from:?} -> {to:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn jlstz_kicks_match_guideline() {
+        for piece in [
+            Tetromino::J,
+            Tetromino::L,
+            Tetromino::S,
+            Tetromino::T,
+            Tetromino::Z,
+        ] {
+            assert_kicks(piece, &JLSTZ_KICKS);
+        }
+    }
+
+    #[test]
+    fn i_kicks_match_guideline() {
+        assert_kicks(Tetromino::I, &I_KICKS);
+    }
+
+    #[test]
+    fn o_never_kicks() {
+        for from in [Zero, Right, Two, Left] {
+            assert_eq!(Tetromino::O.kicks(from, from.cw()).to_vec(), vec![(0, 0)]);
+        }
+    }
+
+    #[test]
+    fn rotation_states_match_guideline() {
+        // Cells of the spawn, right, two and left states in the piece's box, y down
+        let states = [
+            (
+                Tetromino::I,
+                [
+                    [(0, 1), (1, 1), (2, 1), (3, 1)],
+                    [(2, 0), (2, 1), (2, 2), (2, 3)],
+                    [(0, 2), (1, 2), (2, 2), (3, 2)],
+                    [(1, 0), (1, 1), (1, 2), (1, 3)],
+                ],
+            ),
+            (Tetromino::O, [[(0, 0), (1, 0), (0, 1), (1, 1)]; 4]),
+            (
+                Tetromino::T,
+                [
+                    [(1, 0), (0, 1), (1, 1), (2, 1)],
+                    [(1, 0), (1, 1), (2, 1), (1, 2)],
+                    [(0, 1), (1, 1), (2, 1), (1, 2)],
+                    [(1, 0), (0, 1), (1, 1), (1, 2)],
+                ],
+            ),
+            (
+                Tetromino::S,
+                [
+                    [(1, 0), (2, 0), (0, 1), (1, 1)],
+                    [(1, 0), (1, 1), (2, 1), (2, 2)],
+                    [(1, 1), (2, 1), (0, 2), (1, 2)],
+                    [(0, 0), (0, 1), (1, 1), (1, 2)],
+                ],
+            ),
+            (
+                Tetromino::Z,
+                [
+                    [(0, 0), (1, 0), (1, 1), (2, 1)],
+                    [(2, 0), (1, 1), (2, 1), (1, 2)],
+                    [(0, 1), (1, 1), (1, 2), (2, 2)],
+                    [(1, 0), (0, 1), (1, 1), (0, 2)],
+                ],
+            ),
+            (
+                Tetromino::J,
+                [
+                    [(0, 0), (0, 1), (1, 1), (2, 1)],
+                    [(1, 0), (2, 0), (1, 1), (1, 2)],
+                    [(0, 1), (1, 1), (2, 1), (2, 2)],
+                    [(1, 0), (1, 1), (0, 2), (1, 2)],
+                ],
+            ),
+            (
+                Tetromino::L,
+                [
+                    [(2, 0), (0, 1), (1, 1), (2, 1)],
+                    [(1, 0), (1, 1), (1, 2), (2, 2)],
+                    [(0, 1), (1, 1), (2, 1), (0, 2)],
+                    [(0, 0), (1, 0), (1, 1), (1, 2)],
+                ],
+            ),
+        ];
+
+        for (piece, expected) in states {
+            for (rotation, cells) in [Zero, Right, Two, Left].into_iter().zip(expected) {
+                let mut actual = piece.cells(rotation);
+                let mut cells = cells;
+                actual.sort();
+                cells.sort();
+                assert_eq!(actual, cells, "{piece:?} {rotation:?}");
+            }
+        }
+    }
 }